@@ -0,0 +1,250 @@
+use crate::{Dial, InstructionErrorKind, SafeReport, SafeRun, TraceStep};
+use std::f64::consts::PI;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const HIGHLIGHT: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// How to play back a dial animation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AnimationOptions {
+    /// pause after each frame
+    pub delay: Duration,
+    /// 1-based instruction to start drawing from, earlier ones are run without frames
+    pub jump_to: usize,
+    /// highlight the mark with terminal colors
+    pub color: bool,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        AnimationOptions {
+            delay: Duration::from_millis(100),
+            jump_to: 1,
+            color: true,
+        }
+    }
+}
+
+/// Draws the dial as an ASCII ring with the pointer `@`, the mark `0`, and the running
+/// counters underneath
+///
+/// # Examples
+///
+/// ```
+/// let dial = day01::Dial::default();
+/// let frame = day01::render_frame(&dial, &day01::SafeReport::default(), None, false);
+/// assert!(frame.contains('@'));
+/// assert!(frame.contains('0'));
+/// assert!(frame.contains("position 50"));
+/// ```
+pub fn render_frame(
+    dial: &Dial,
+    report: &SafeReport,
+    step: Option<&TraceStep>,
+    color: bool,
+) -> String {
+    // twice as wide as tall since terminal cells are about twice as tall as wide
+    let radius_y = 10.0_f64;
+    let radius_x = radius_y * 2.0;
+    let height = (radius_y * 2.0) as usize + 1;
+    let width = (radius_x * 2.0) as usize + 1;
+    let mut grid = vec![vec![' '; width]; height];
+
+    let cell = |position: u32| -> (usize, usize) {
+        // 0 at the top, turning right goes clockwise
+        let angle = 2.0 * PI * f64::from(position) / f64::from(dial.size()) - PI / 2.0;
+        let x = (radius_x + radius_x * angle.cos()).round() as usize;
+        let y = (radius_y + radius_y * angle.sin()).round() as usize;
+        (y.min(height - 1), x.min(width - 1))
+    };
+
+    for position in 0..dial.size() {
+        let (y, x) = cell(position);
+        grid[y][x] = '.';
+    }
+    let (mark_y, mark_x) = cell(dial.mark());
+    grid[mark_y][mark_x] = '0';
+    let (pointer_y, pointer_x) = cell(dial.position());
+    grid[pointer_y][pointer_x] = '@';
+
+    let mut frame = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        let mut line = String::new();
+        for (x, &c) in row.iter().enumerate() {
+            if color && (y, x) == (mark_y, mark_x) {
+                line.push_str(HIGHLIGHT);
+                line.push(c);
+                line.push_str(RESET);
+            } else {
+                line.push(c);
+            }
+        }
+        frame.push_str(line.trim_end());
+        frame.push('\n');
+    }
+
+    frame.push_str(&format!(
+        "position {}  rests at zero {}  passes zero {}\n",
+        dial.position(),
+        report.rests_at_zero,
+        report.passes_zero
+    ));
+    match step {
+        Some(step) => frame.push_str(&format!(
+            "instruction {} (line {}): {}{} from {} to {}, {} zeroes\n",
+            report.instructions,
+            step.line,
+            step.direction,
+            step.clicks,
+            step.position_before,
+            step.position_after,
+            step.zeroes_passed
+        )),
+        None => frame.push_str("instruction 0\n"),
+    }
+    frame
+}
+
+/// Replays an instruction stream on the dial, drawing a frame per instruction.
+/// The dial moves with the same rotation used by check_safe, so the final report matches it.
+/// Blank lines are passed over, any other bad line stops the animation with an InvalidData
+/// error.
+///
+/// # Examples
+///
+/// ```
+/// let options = day01::AnimationOptions {
+///     delay: std::time::Duration::ZERO,
+///     jump_to: 2,
+///     color: false,
+/// };
+/// let mut out: Vec<u8> = Vec::new();
+/// let input = "L68\nL30\nR48\n".as_bytes();
+/// let report = day01::animate(&mut out, day01::Dial::default(), input, options).unwrap();
+/// assert_eq!(report.final_position, 0);
+/// let text = String::from_utf8(out).unwrap();
+/// assert!(!text.contains("instruction 1 "));
+/// assert!(text.contains("instruction 3 (line 3): R48 from 52 to 0, 1 zeroes"));
+/// ```
+pub fn animate<W: Write, R: BufRead>(
+    out: &mut W,
+    dial: Dial,
+    input: R,
+    options: AnimationOptions,
+) -> io::Result<SafeReport> {
+    let mut run = SafeRun::new(dial, input);
+    if options.jump_to <= 1 {
+        draw(out, &run, None, options)?;
+    }
+    while let Some(step) = run.next() {
+        let step = match step {
+            Ok(step) => step,
+            Err(e) if e.kind == InstructionErrorKind::Empty => continue,
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        // counted in instructions run, so blank lines don't shift the jump
+        if run.report().instructions >= options.jump_to as u64 {
+            draw(out, &run, Some(&step), options)?;
+        }
+    }
+    Ok(run.report())
+}
+
+fn draw<W: Write, R: BufRead>(
+    out: &mut W,
+    run: &SafeRun<R>,
+    step: Option<&TraceStep>,
+    options: AnimationOptions,
+) -> io::Result<()> {
+    let frame = render_frame(run.dial(), &run.report(), step, options.color);
+    write!(out, "{CLEAR_SCREEN}{frame}")?;
+    out.flush()?;
+    if !options.delay.is_zero() {
+        thread::sleep(options.delay);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_safe;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::Path;
+
+    fn quiet() -> AnimationOptions {
+        AnimationOptions {
+            delay: Duration::ZERO,
+            jump_to: 1,
+            color: false,
+        }
+    }
+
+    #[test]
+    fn animate_matches_check_safe() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = BufReader::new(File::open(&path).expect("test2.txt file missing"));
+        let mut out: Vec<u8> = Vec::new();
+        let report = animate(&mut out, Dial::default(), data, quiet()).unwrap();
+        let expected = check_safe(File::open(&path).expect("test2.txt file missing")).unwrap();
+        assert_eq!(report, expected);
+
+        let frames = String::from_utf8(out)
+            .unwrap()
+            .matches(CLEAR_SCREEN)
+            .count();
+        assert_eq!(frames as u64, expected.instructions + 1);
+    }
+
+    #[test]
+    fn animate_skips_frames_before_jump() {
+        let options = AnimationOptions {
+            jump_to: 4,
+            ..quiet()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        let input = "R1\nR1\nR1\nR1\nR1\n".as_bytes();
+        animate(&mut out, Dial::default(), input, options).unwrap();
+        let frames = String::from_utf8(out)
+            .unwrap()
+            .matches(CLEAR_SCREEN)
+            .count();
+        assert_eq!(frames, 2);
+    }
+
+    #[test]
+    fn animate_jumps_by_instruction_not_line() {
+        let options = AnimationOptions {
+            jump_to: 2,
+            ..quiet()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        let input = "R1
+
+R2
+R3
+"
+        .as_bytes();
+        animate(&mut out, Dial::default(), input, options).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(CLEAR_SCREEN).count(), 2);
+        assert!(text.contains("instruction 2 (line 3): R2 from 51 to 53"));
+    }
+
+    #[test]
+    fn render_frame_highlights_mark() {
+        let dial = Dial::new(100, 25, 0);
+        let frame = render_frame(&dial, &SafeReport::default(), None, true);
+        assert!(frame.contains(&format!("{HIGHLIGHT}0{RESET}")));
+        let plain = render_frame(&dial, &SafeReport::default(), None, false);
+        assert!(!plain.contains(HIGHLIGHT));
+        // the mark is drawn at the top of the ring and the pointer on the right
+        assert_eq!(plain.lines().next().unwrap().chars().nth(20), Some('0'));
+        assert!(plain.lines().nth(10).unwrap().ends_with('@'));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::trace::{csv_field, json_string};
+use crate::{InstructionError, InstructionErrorKind, SafeReport, run_skipping_errors};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The outcome of checking one file of a batch. The report covers the lines that
+/// parsed, and a file that can't be opened at all gets a single error on line 0.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AuditRow {
+    pub path: PathBuf,
+    pub report: SafeReport,
+    pub errors: Vec<InstructionError>,
+}
+
+/// Expands a directory, a glob like `locks/*.txt`, or a single file path into the
+/// files to audit, sorted by name.
+/// Only the last path component may contain `*` or `?` wildcards.
+pub fn audit_paths(spec: &str) -> io::Result<Vec<PathBuf>> {
+    let path = Path::new(spec);
+    let mut result: Vec<PathBuf> = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_file() {
+                result.push(entry_path);
+            }
+        }
+    } else if spec.contains(['*', '?']) {
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let pattern: Vec<char> = path
+            .file_name()
+            .map(|n| n.to_string_lossy().chars().collect())
+            .unwrap_or_default();
+        for entry in fs::read_dir(parent)? {
+            let entry_path = entry?.path();
+            let name: Vec<char> = entry_path
+                .file_name()
+                .map(|n| n.to_string_lossy().chars().collect())
+                .unwrap_or_default();
+            if entry_path.is_file() && glob_match(&pattern, &name) {
+                result.push(entry_path);
+            }
+        }
+    } else {
+        result.push(path.to_path_buf());
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Runs every file through check_safe's dial, carrying on past files with bad lines
+pub fn audit_files(paths: &[PathBuf]) -> Vec<AuditRow> {
+    let mut rows: Vec<AuditRow> = Vec::new();
+    for path in paths {
+        let row = match File::open(path) {
+            Ok(file) => {
+                let (report, errors) = run_skipping_errors(file);
+                AuditRow {
+                    path: path.clone(),
+                    report,
+                    errors,
+                }
+            }
+            Err(e) => AuditRow {
+                path: path.clone(),
+                report: SafeReport::default(),
+                errors: vec![InstructionError {
+                    line: 0,
+                    column: 0,
+                    text: e.to_string(),
+                    kind: InstructionErrorKind::Unreadable,
+                }],
+            },
+        };
+        rows.push(row);
+    }
+    rows
+}
+
+/// Writes the audit as an aligned table, followed by every error found
+pub fn write_audit_text<W: Write>(out: &mut W, rows: &[AuditRow]) -> io::Result<()> {
+    let width = rows
+        .iter()
+        .map(|r| r.path.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("file".len());
+    writeln!(
+        out,
+        "{:<width$}  {:>12}  {:>13}  {:>11}  {:>14}  {:>6}",
+        "file", "instructions", "rests_at_zero", "passes_zero", "final_position", "errors"
+    )?;
+    for row in rows {
+        writeln!(
+            out,
+            "{:<width$}  {:>12}  {:>13}  {:>11}  {:>14}  {:>6}",
+            row.path.display().to_string(),
+            row.report.instructions,
+            row.report.rests_at_zero,
+            row.report.passes_zero,
+            row.report.final_position,
+            row.errors.len()
+        )?;
+    }
+    for row in rows {
+        for error in &row.errors {
+            writeln!(out, "{}: {error}", row.path.display())?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the audit as CSV with a header row, keeping only the first error of each file
+pub fn write_audit_csv<W: Write>(out: &mut W, rows: &[AuditRow]) -> io::Result<()> {
+    writeln!(
+        out,
+        "file,instructions,rests_at_zero,passes_zero,final_position,errors,first_error"
+    )?;
+    for row in rows {
+        let first_error = row
+            .errors
+            .first()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            csv_field(&row.path.display().to_string()),
+            row.report.instructions,
+            row.report.rests_at_zero,
+            row.report.passes_zero,
+            row.report.final_position,
+            row.errors.len(),
+            csv_field(&first_error)
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the audit as a JSON array with one object per file
+pub fn write_audit_json<W: Write>(out: &mut W, rows: &[AuditRow]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, row) in rows.iter().enumerate() {
+        let errors: Vec<String> = row
+            .errors
+            .iter()
+            .map(|e| json_string(&e.to_string()))
+            .collect();
+        let separator = if i + 1 < rows.len() { "," } else { "" };
+        writeln!(
+            out,
+            "  {{\"file\":{},\"instructions\":{},\"rests_at_zero\":{},\"passes_zero\":{},\"final_position\":{},\"errors\":[{}]}}{separator}",
+            json_string(&row.path.display().to_string()),
+            row.report.instructions,
+            row.report.rests_at_zero,
+            row.report.passes_zero,
+            row.report.final_position,
+            errors.join(",")
+        )?;
+    }
+    writeln!(out, "]")?;
+    Ok(())
+}
+
+// matches a file name against a pattern where `*` is any run of characters and `?` is one.
+// On a mismatch only the last `*` is retried, letting it swallow one more character, which
+// keeps the match linear in the pattern times the name.
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // position of the last star seen and where its run of the name currently ends
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match last_star {
+                Some((star, star_end)) => {
+                    last_star = Some((star, star_end + 1));
+                    p = star + 1;
+                    n = star_end + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_files() -> Vec<PathBuf> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        [
+            "test1.txt",
+            "test2.txt",
+            "test3.txt",
+            "test4.txt",
+            "test5.txt",
+        ]
+        .iter()
+        .map(|name| src.join(name))
+        .collect()
+    }
+
+    #[test]
+    fn audit_files_continues_past_bad_files() {
+        let mut paths = test_files();
+        paths.push(PathBuf::from("no/such/file.txt"));
+        let rows = audit_files(&paths);
+        assert_eq!(rows.len(), 6);
+        assert!(rows[0].path.ends_with("test1.txt"));
+        assert!(rows[0].errors.is_empty());
+        assert_eq!(rows[0].report.passes_zero, 6);
+        assert_eq!(rows[1].report.final_position, 50);
+        assert_eq!(rows[2].errors.len(), 3);
+        assert_eq!(rows[5].errors[0].line, 0);
+    }
+
+    #[test]
+    fn audit_paths_lists_directories() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let paths = audit_paths(&dir.to_string_lossy()).unwrap();
+        assert!(paths.iter().any(|p| p.ends_with("lib.rs")));
+        assert!(paths.iter().all(|p| p.is_file()));
+    }
+
+    #[test]
+    fn audit_paths_expands_globs() {
+        let pattern = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test?.txt");
+        let paths = audit_paths(&pattern.to_string_lossy()).unwrap();
+        assert!(paths.iter().any(|p| p.ends_with("test1.txt")));
+        assert!(paths.iter().all(|p| p.extension().unwrap() == "txt"));
+        assert!(paths.is_sorted());
+    }
+
+    #[test]
+    fn writers_include_every_file() {
+        let rows = audit_files(&test_files()[..3]);
+        let mut text: Vec<u8> = Vec::new();
+        write_audit_text(&mut text, &rows).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.lines().next().unwrap().starts_with("file "));
+        assert!(text.contains("line 3, column 2: click count is not a number \"1O\""));
+
+        let mut csv: Vec<u8> = Vec::new();
+        write_audit_csv(&mut csv, &rows).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",10,3,6,32,0,"));
+
+        let mut json: Vec<u8> = Vec::new();
+        write_audit_json(&mut json, &rows).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert_eq!(json.matches("\"file\":").count(), 3);
+        assert!(json.contains("\"final_position\":32,\"errors\":[]}"));
+    }
+
+    #[test]
+    fn glob_match_handles_wildcards() {
+        let matches = |pattern: &str, name: &str| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            glob_match(&pattern, &name)
+        };
+        assert!(matches("*.txt", "lock1.txt"));
+        assert!(matches("lock?.txt", "lock1.txt"));
+        assert!(!matches("lock?.txt", "lock12.txt"));
+        assert!(matches("*", ""));
+        assert!(!matches("*.txt", "lock1.csv"));
+        assert!(matches("*lock*1*", "a_lock_b1"));
+        assert!(matches("a*b?c**", "aXbbYc"));
+        assert!(!matches("a*b?c", "abc"));
+        // needs backtracking past the first `b`
+        assert!(matches("*ab", "aab"));
+        // hopeless for the old recursive matcher
+        let stars = "*a".repeat(30) + "b";
+        assert!(!matches(&stars, &"a".repeat(60)));
+    }
+}
@@ -0,0 +1,56 @@
+//! Replays a safe instruction file as a terminal animation.
+//!
+//! Usage: animate <file> [--delay-ms N] [--jump N] [--no-color]
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process::ExitCode;
+use std::time::Duration;
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut path: Option<String> = None;
+    let mut options = day01::AnimationOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delay-ms" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(ms) => options.delay = Duration::from_millis(ms),
+                None => return usage("--delay-ms needs a number of milliseconds"),
+            },
+            "--jump" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => options.jump_to = n,
+                None => return usage("--jump needs an instruction number"),
+            },
+            "--no-color" => options.color = false,
+            _ if path.is_none() => path = Some(arg),
+            _ => return usage(&format!("unexpected argument {arg}")),
+        }
+    }
+
+    let Some(path) = path else {
+        return usage("missing instruction file");
+    };
+    let input = match File::open(&path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            eprintln!("unable to open {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut out = io::stdout().lock();
+    match day01::animate(&mut out, day01::Dial::default(), input, options) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage(problem: &str) -> ExitCode {
+    eprintln!("{problem}");
+    eprintln!("usage: animate <file> [--delay-ms N] [--jump N] [--no-color]");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,61 @@
+//! Runs every safe instruction file in a directory or glob and prints a summary table.
+//!
+//! Usage: audit <directory-or-glob>... [--format text|csv|json]
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut specs: Vec<String> = Vec::new();
+    let mut format = String::from("text");
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next() {
+                Some(f) if ["text", "csv", "json"].contains(&f.as_str()) => format = f,
+                _ => return usage("--format needs one of text, csv or json"),
+            },
+            _ => specs.push(arg),
+        }
+    }
+    if specs.is_empty() {
+        return usage("missing directory or glob");
+    }
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for spec in &specs {
+        match day01::audit_paths(spec) {
+            Ok(found) => paths.extend(found),
+            Err(e) => {
+                eprintln!("unable to list {spec}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let rows = day01::audit_files(&paths);
+    let mut out = io::stdout().lock();
+    let written = match format.as_str() {
+        "csv" => day01::write_audit_csv(&mut out, &rows),
+        "json" => day01::write_audit_json(&mut out, &rows),
+        _ => day01::write_audit_text(&mut out, &rows),
+    };
+    if let Err(e) = written {
+        eprintln!("unable to write summary: {e}");
+        return ExitCode::FAILURE;
+    }
+    if rows.iter().any(|r| !r.errors.is_empty()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn usage(problem: &str) -> ExitCode {
+    eprintln!("{problem}");
+    eprintln!("usage: audit <directory-or-glob>... [--format text|csv|json]");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,234 @@
+use crate::{
+    Dial, InstructionError, InstructionErrorKind, SafeReport, parse_numbered_line, unreadable_line,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// A safe with several named wheels, each its own Dial
+///
+/// # Examples
+///
+/// ```
+/// let mut lock = day01::CombinationLock::new();
+/// lock.add_wheel("A", day01::Dial::default());
+/// lock.add_wheel("B", day01::Dial::new(10, 0, 0));
+/// let report = lock.run("A:L50\nB:R25\nA:R1\n".as_bytes()).unwrap();
+/// assert_eq!(report.steps[1].positions, [0, 5]);
+/// assert_eq!(report.wheels[0].report.rests_at_zero, 1);
+/// assert_eq!(report.wheels[1].report.passes_zero, 2);
+/// assert_eq!(lock.positions(), [1, 5]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CombinationLock {
+    names: Vec<String>,
+    dials: Vec<Dial>,
+}
+
+/// Totals for a single wheel of a CombinationLock
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WheelReport {
+    pub name: String,
+    pub report: SafeReport,
+}
+
+/// The state of every wheel after one instruction, in the order the wheels were added
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CombinationStep {
+    pub line: usize,
+    pub wheel: String,
+    pub direction: char,
+    pub clicks: u64,
+    pub zeroes_passed: u64,
+    pub positions: Vec<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CombinationReport {
+    pub wheels: Vec<WheelReport>,
+    pub steps: Vec<CombinationStep>,
+}
+
+impl CombinationLock {
+    pub fn new() -> CombinationLock {
+        CombinationLock::default()
+    }
+
+    /// Adds a wheel, replacing any wheel that already has this name
+    pub fn add_wheel(&mut self, name: &str, dial: Dial) {
+        match self.index_of(name) {
+            Some(i) => self.dials[i] = dial,
+            None => {
+                self.names.push(name.to_string());
+                self.dials.push(dial);
+            }
+        }
+    }
+
+    pub fn wheel(&self, name: &str) -> Option<&Dial> {
+        self.index_of(name).map(|i| &self.dials[i])
+    }
+
+    pub fn positions(&self) -> Vec<u32> {
+        self.dials.iter().map(|d| d.position()).collect()
+    }
+
+    /// Turns one wheel, returning its mark hits or None if there is no such wheel
+    pub fn rotate(&mut self, name: &str, clicks: u64, direction: char) -> Option<u64> {
+        let i = self.index_of(name)?;
+        Some(self.dials[i].rotate(clicks, direction))
+    }
+
+    /// Runs `wheel:instruction` lines, stopping at the first bad line
+    pub fn run<R: BufRead>(&mut self, input: R) -> Result<CombinationReport, InstructionError> {
+        let mut reports: Vec<SafeReport> = self
+            .dials
+            .iter()
+            .map(|d| SafeReport {
+                final_position: d.position(),
+                ..SafeReport::default()
+            })
+            .collect();
+        let mut steps: Vec<CombinationStep> = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let line_number = i + 1;
+            let text = line.map_err(|e| unreadable_line(line_number, e))?;
+            let (wheel, direction, clicks) = parse_numbered_wheel_line(line_number, &text)?;
+            let index = self.index_of(&wheel).ok_or_else(|| InstructionError {
+                line: line_number,
+                column: 1,
+                text: wheel.clone(),
+                kind: InstructionErrorKind::UnknownWheel,
+            })?;
+            let zeroes_passed = self.dials[index].rotate(clicks, direction);
+            reports[index].record(&self.dials[index], zeroes_passed);
+            steps.push(CombinationStep {
+                line: line_number,
+                wheel,
+                direction,
+                clicks,
+                zeroes_passed,
+                positions: self.positions(),
+            });
+        }
+
+        let wheels = self
+            .names
+            .iter()
+            .zip(reports)
+            .map(|(name, report)| WheelReport {
+                name: name.clone(),
+                report,
+            })
+            .collect();
+        Ok(CombinationReport { wheels, steps })
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+}
+
+/// Parses a line like "A:R30" into its wheel name, direction and click count
+///
+/// # Examples
+///
+/// ```
+/// let result = day01::parse_wheel_line("B:L5");
+/// assert_eq!(result, Ok((String::from("B"), 'L', 5)));
+/// ```
+/// ```
+/// let error = day01::parse_wheel_line("B:L5x").unwrap_err();
+/// assert_eq!(error.column, 4);
+/// ```
+pub fn parse_wheel_line(input: &str) -> Result<(String, char, u64), InstructionError> {
+    parse_numbered_wheel_line(1, input)
+}
+
+fn parse_numbered_wheel_line(
+    line: usize,
+    input: &str,
+) -> Result<(String, char, u64), InstructionError> {
+    let missing_wheel = || InstructionError {
+        line,
+        column: 1,
+        text: input.to_string(),
+        kind: InstructionErrorKind::MissingWheel,
+    };
+    let (name, instruction) = input.split_once(':').ok_or_else(missing_wheel)?;
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(missing_wheel());
+    }
+    let (direction, clicks) = parse_numbered_line(line, instruction).map_err(|mut e| {
+        // point at the instruction's position in the full line
+        e.column += name.chars().count() + 1;
+        e
+    })?;
+    Ok((name.to_string(), direction, clicks))
+}
+
+/// Runs a multi-wheel instruction file through the given lock
+pub fn check_combination(
+    lock: &mut CombinationLock,
+    input: File,
+) -> Result<CombinationReport, InstructionError> {
+    lock.run(BufReader::new(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn example_lock() -> CombinationLock {
+        let mut lock = CombinationLock::new();
+        lock.add_wheel("A", Dial::default());
+        lock.add_wheel("B", Dial::new(20, 10, 0));
+        lock.add_wheel("C", Dial::new(5, 0, 3));
+        lock
+    }
+
+    #[test]
+    fn check_combination_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test5.txt");
+        let data = File::open(path).expect("test5.txt file missing");
+        let mut lock = example_lock();
+        let result = check_combination(&mut lock, data).expect("bad instruction");
+        let last = result.steps.last().unwrap();
+        assert_eq!(result.steps.len(), 6);
+        assert_eq!(last.positions, [32, 10, 3]);
+        assert_eq!(result.steps[1].positions, [82, 0, 0]);
+
+        let a = &result.wheels[0].report;
+        assert_eq!((a.rests_at_zero, a.passes_zero, a.instructions), (0, 2, 2));
+        let b = &result.wheels[1].report;
+        assert_eq!((b.rests_at_zero, b.passes_zero, b.instructions), (1, 2, 2));
+        let c = &result.wheels[2].report;
+        assert_eq!((c.rests_at_zero, c.passes_zero, c.instructions), (2, 3, 2));
+    }
+
+    #[test]
+    fn run_rejects_unknown_wheels() {
+        let mut lock = example_lock();
+        let error = lock.run("A:R5\nD:R5\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.kind, InstructionErrorKind::UnknownWheel);
+    }
+
+    #[test]
+    fn parse_wheel_line_rejects_missing_wheel() {
+        let kind = |input: &str| parse_wheel_line(input).unwrap_err().kind;
+        assert_eq!(kind("R30"), InstructionErrorKind::MissingWheel);
+        assert_eq!(kind(":R30"), InstructionErrorKind::MissingWheel);
+        assert_eq!(kind("A B:R30"), InstructionErrorKind::MissingWheel);
+        assert_eq!(kind("A:"), InstructionErrorKind::Empty);
+    }
+
+    #[test]
+    fn add_wheel_replaces_existing_name() {
+        let mut lock = example_lock();
+        lock.add_wheel("B", Dial::new(8, 2, 0));
+        assert_eq!(lock.positions(), [50, 2, 0]);
+        assert_eq!(lock.wheel("B").map(|d| d.size()), Some(8));
+    }
+}
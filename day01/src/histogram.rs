@@ -0,0 +1,170 @@
+use crate::{Dial, InstructionError, run_until_error};
+use std::collections::BTreeSet;
+use std::fs::File;
+
+/// How often a single dial position was clicked onto and rested on
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PositionCounts {
+    /// every click that landed on the position, including the ones we rested on
+    pub passes: u64,
+    /// instructions that ended on the position
+    pub rests: u64,
+}
+
+/// Visit counts for every position of a dial, indexed by position
+///
+/// # Examples
+///
+/// ```
+/// let mut histogram = day01::Histogram::new(10);
+/// histogram.record(2, 15, 'R');
+/// assert_eq!(histogram.counts(3), day01::PositionCounts { passes: 2, rests: 0 });
+/// assert_eq!(histogram.counts(7), day01::PositionCounts { passes: 2, rests: 1 });
+/// assert_eq!(histogram.counts(8), day01::PositionCounts { passes: 1, rests: 0 });
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Histogram {
+    pub positions: Vec<PositionCounts>,
+}
+
+impl Histogram {
+    /// Creates an empty histogram for a dial of `size` positions.
+    /// Panics if `size` is 0.
+    pub fn new(size: u32) -> Histogram {
+        assert!(size > 0, "histogram needs at least one position");
+        Histogram {
+            positions: vec![PositionCounts::default(); size as usize],
+        }
+    }
+
+    /// Adds one instruction starting from `start`, without walking every click
+    pub fn record(&mut self, start: u32, clicks: u64, direction: char) {
+        let size = self.positions.len() as u64;
+        // every full turn lands on each position once
+        let full_turns = clicks / size;
+        if full_turns > 0 {
+            for counts in self.positions.iter_mut() {
+                counts.passes += full_turns;
+            }
+        }
+        let leftover = clicks % size;
+        let start = u64::from(start) % size;
+        for k in 1..=leftover {
+            let position = match direction {
+                'R' => (start + k) % size,
+                'L' => (start + size - k) % size,
+                _ => panic!("invalid direction {direction}"),
+            };
+            self.positions[position as usize].passes += 1;
+        }
+        let end = match direction {
+            'R' => (start + leftover) % size,
+            'L' => (start + size - leftover) % size,
+            _ => panic!("invalid direction {direction}"),
+        };
+        self.positions[end as usize].rests += 1;
+    }
+
+    /// Counts for one position, which is wrapped onto the dial
+    pub fn counts(&self, position: u32) -> PositionCounts {
+        self.positions[position as usize % self.positions.len()]
+    }
+
+    /// Combined counts over a set of trigger positions, each distinct position counted once
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::default();
+    /// let histogram = dial.run_histogram(vec![('L', 50), ('R', 25), ('R', 25)]);
+    /// let hits = histogram.trigger_hits(&[0, 25, 25, 75]);
+    /// assert_eq!(hits, day01::PositionCounts { passes: 3, rests: 2 });
+    /// ```
+    pub fn trigger_hits(&self, triggers: &[u32]) -> PositionCounts {
+        let size = self.positions.len() as u32;
+        let distinct: BTreeSet<u32> = triggers.iter().map(|t| t % size).collect();
+        let mut total = PositionCounts::default();
+        for position in distinct {
+            let counts = self.counts(position);
+            total.passes += counts.passes;
+            total.rests += counts.rests;
+        }
+        total
+    }
+}
+
+impl Dial {
+    /// Runs a series of `(direction, clicks)` instructions, counting visits to every position
+    pub fn run_histogram<I: IntoIterator<Item = (char, u64)>>(
+        &mut self,
+        instructions: I,
+    ) -> Histogram {
+        let mut histogram = Histogram::new(self.size());
+        for (direction, clicks) in instructions {
+            histogram.record(self.position(), clicks, direction);
+            self.rotate(clicks, direction);
+        }
+        histogram
+    }
+}
+
+/// Builds the visit histogram of a file on the standard dial, stopping at the first bad
+/// line
+pub fn histogram_safe(input: File) -> Result<Histogram, InstructionError> {
+    let mut dial = Dial::default();
+    run_until_error(input, |instructions| dial.run_histogram(instructions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_safe;
+    use std::path::Path;
+
+    #[test]
+    fn histogram_safe_agrees_with_check_safe() {
+        for name in ["src/test1.txt", "src/test2.txt"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+            let histogram = histogram_safe(File::open(&path).expect("test file missing"))
+                .expect("bad instruction");
+            let report =
+                check_safe(File::open(&path).expect("test file missing")).expect("bad instruction");
+            let zero = histogram.counts(0);
+            assert_eq!(zero.passes, report.passes_zero);
+            assert_eq!(zero.rests, report.rests_at_zero);
+            let rests: u64 = histogram.positions.iter().map(|c| c.rests).sum();
+            assert_eq!(rests, report.instructions);
+        }
+    }
+
+    #[test]
+    fn histogram_safe_reports_bad_lines() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test3.txt");
+        let data = File::open(path).expect("test3.txt file missing");
+        assert!(histogram_safe(data).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn histogram_panics_on_zero_size() {
+        Histogram::new(0);
+    }
+
+    #[test]
+    fn histogram_matches_clicking_one_at_a_time() {
+        let instructions = [('R', 7), ('L', 23), ('R', 40), ('L', 0), ('L', 11)];
+        let mut dial = Dial::new(12, 5, 0);
+        let histogram = dial.run_histogram(instructions);
+
+        let mut expected = vec![PositionCounts::default(); 12];
+        let mut position: i64 = 5;
+        for (direction, clicks) in instructions {
+            for _ in 0..clicks {
+                position += if direction == 'R' { 1 } else { -1 };
+                expected[position.rem_euclid(12) as usize].passes += 1;
+            }
+            expected[position.rem_euclid(12) as usize].rests += 1;
+        }
+        assert_eq!(histogram.positions, expected);
+    }
+}
@@ -1,65 +1,440 @@
-use std::{fs::File, io::BufRead, io::BufReader};
-
-pub fn rotate(start: u32, clicks: u32, direction: char) -> (u32, u32) {
-    let mut position: i32 = start as i32;
-    let mut zeroes: u32 = 0;
-    //we can discard batches of 100 clicks since they end up at same place on dial
-    let mut relevant_clicks = clicks;
-    while relevant_clicks > 100 {
-        relevant_clicks = relevant_clicks - 100;
-        zeroes += 1;
-    }
-
-    if direction == 'R' {
-        // turning right, going higher, might pass 99
-        for _ in 0..=relevant_clicks - 1 {
-            position += 1;
-            if position == 100 {
-                position = 0;
-            }
-            if position == 0 {
-                zeroes += 1;
-            }
+mod animation;
+mod batch;
+mod combination;
+mod histogram;
+mod optimizer;
+mod program;
+mod solver;
+mod trace;
+
+pub use animation::{AnimationOptions, animate, render_frame};
+pub use batch::{
+    AuditRow, audit_files, audit_paths, write_audit_csv, write_audit_json, write_audit_text,
+};
+pub use combination::{
+    CombinationLock, CombinationReport, CombinationStep, WheelReport, check_combination,
+    parse_wheel_line,
+};
+pub use histogram::{Histogram, PositionCounts, histogram_safe};
+pub use optimizer::{Equivalence, Simplification, simplify};
+pub use program::{Instructions, Program, Step, check_safe_program, parse_program};
+pub use solver::{PassRule, solve_combination};
+pub use trace::{SafeRun, TraceStep, write_csv_trace, write_json_lines_trace};
+
+use std::{error::Error, fmt, fs::File, io, io::BufRead, io::BufReader};
+
+/// Summary of running a list of instructions through a dial
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SafeReport {
+    /// instructions that left the dial resting on the mark
+    pub rests_at_zero: u64,
+    /// every click that landed on the mark, including the ones we rested on
+    pub passes_zero: u64,
+    pub final_position: u32,
+    pub instructions: u64,
+}
+
+impl SafeReport {
+    // adds one instruction that just moved the dial and hit its mark `hits` times
+    pub(crate) fn record(&mut self, dial: &Dial, hits: u64) {
+        self.passes_zero += hits;
+        if dial.position() == dial.mark() {
+            self.rests_at_zero += 1;
         }
-    } else if direction == 'L' {
-        //turning left, going lower, might pass 0
-        for _ in 0..=relevant_clicks - 1 {
-            position -= 1;
-            if position == -1 {
-                position = 99;
-            }
-            if position == 0 {
-                zeroes += 1;
+        self.instructions += 1;
+        self.final_position = dial.position();
+    }
+}
+
+/// A safe dial with `size` positions numbered from 0, a starting position and a mark
+/// whose hits we want to count.
+///
+/// # Examples
+///
+/// ```
+/// let mut dial = day01::Dial::new(10, 3, 0);
+/// let hits = dial.rotate(25, 'R');
+/// assert_eq!(dial.position(), 8);
+/// assert_eq!(hits, 2);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Dial {
+    size: u32,
+    start: u32,
+    mark: u32,
+    position: u32,
+}
+
+impl Dial {
+    /// Creates a dial resting on `start`, wrapping `start` and `mark` onto the dial.
+    /// Panics if `size` is 0.
+    pub fn new(size: u32, start: u32, mark: u32) -> Dial {
+        assert!(size > 0, "dial needs at least one position");
+        Dial {
+            size,
+            start: start % size,
+            mark: mark % size,
+            position: start % size,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn mark(&self) -> u32 {
+        self.mark
+    }
+
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+
+    /// Puts the dial back on its starting position
+    pub fn reset(&mut self) {
+        self.position = self.start;
+    }
+
+    /// Turns the dial and returns how many clicks landed on the mark
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::default();
+    /// assert_eq!(dial.rotate(68, 'L'), 1);
+    /// assert_eq!(dial.position(), 82);
+    /// ```
+    pub fn rotate(&mut self, clicks: u64, direction: char) -> u64 {
+        let size = u64::from(self.size);
+        let position = u64::from(self.position);
+        let mark = u64::from(self.mark);
+        // how many clicks in the direction of travel until we first reach the mark,
+        //   a full turn if we are already sitting on it
+        let to_mark = if direction == 'R' {
+            (mark + size - position) % size
+        } else if direction == 'L' {
+            (position + size - mark) % size
+        } else {
+            panic!("invalid direction {direction}")
+        };
+        let first_hit = if to_mark == 0 { size } else { to_mark };
+        // after the first hit we land on the mark again once every full turn
+        let hits = if clicks < first_hit {
+            0
+        } else {
+            (clicks - first_hit) / size + 1
+        };
+
+        let offset = clicks % size;
+        let new_position = if direction == 'R' {
+            (position + offset) % size
+        } else {
+            (position + size - offset) % size
+        };
+        self.position = new_position as u32;
+        hits
+    }
+
+    /// Runs a series of `(direction, clicks)` instructions, returning the total mark hits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::new(12, 0, 6);
+    /// let hits = dial.apply(vec![('R', 6), ('L', 12), ('R', 1)]);
+    /// assert_eq!(hits, 2);
+    /// assert_eq!(dial.position(), 7);
+    /// ```
+    pub fn apply<I: IntoIterator<Item = (char, u64)>>(&mut self, instructions: I) -> u64 {
+        let mut hits: u64 = 0;
+        for (direction, clicks) in instructions {
+            hits += self.rotate(clicks, direction);
+        }
+        hits
+    }
+
+    /// Runs a series of `(direction, clicks)` instructions and reports both the
+    /// resting and passing mark counts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::default();
+    /// let report = dial.run(vec![('L', 50), ('R', 250), ('L', 51)]);
+    /// assert_eq!(report.rests_at_zero, 1);
+    /// assert_eq!(report.passes_zero, 4);
+    /// assert_eq!(report.final_position, 99);
+    /// assert_eq!(report.instructions, 3);
+    /// ```
+    pub fn run<I: IntoIterator<Item = (char, u64)>>(&mut self, instructions: I) -> SafeReport {
+        let mut report = SafeReport {
+            final_position: self.position,
+            ..SafeReport::default()
+        };
+        for (direction, clicks) in instructions {
+            let hits = self.rotate(clicks, direction);
+            report.record(self, hits);
+        }
+        report
+    }
+
+    /// Turns the dial back over an instruction it already ran, returning how many clicks
+    /// of the original instruction landed on the mark
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::default();
+    /// let hits = dial.rotate(160, 'R');
+    /// assert_eq!(dial.undo(160, 'R'), hits);
+    /// assert_eq!(dial.position(), 50);
+    /// ```
+    pub fn undo(&mut self, clicks: u64, direction: char) -> u64 {
+        let opposite = match direction {
+            'R' => 'L',
+            'L' => 'R',
+            _ => panic!("invalid direction {direction}"),
+        };
+        self.rotate(clicks, opposite);
+        // going backwards visits the start but not the end of the original move,
+        //   so replay it forwards to count the same clicks the original did
+        let mut replay = *self;
+        replay.rotate(clicks, direction)
+    }
+
+    /// Undoes a whole program, last instruction first, leaving the dial where the
+    /// program must have started. The report describes the program run forwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dial = day01::Dial::new(100, 99, 0);
+    /// let report = dial.rewind(&[('L', 50), ('R', 250), ('L', 51)]);
+    /// assert_eq!(dial.position(), 50);
+    /// assert_eq!(report.rests_at_zero, 1);
+    /// assert_eq!(report.passes_zero, 4);
+    /// assert_eq!(report.final_position, 99);
+    /// ```
+    pub fn rewind(&mut self, instructions: &[(char, u64)]) -> SafeReport {
+        let mut report = SafeReport {
+            final_position: self.position,
+            instructions: instructions.len() as u64,
+            ..SafeReport::default()
+        };
+        for &(direction, clicks) in instructions.iter().rev() {
+            if self.position == self.mark {
+                report.rests_at_zero += 1;
             }
+            report.passes_zero += self.undo(clicks, direction);
         }
-    } else {
-        panic!("invalid direction {direction}")
+        report
     }
-    //println!("{direction} to position {position} and encountered {zeroes} zeroes");
-    (position as u32, zeroes)
 }
 
-pub fn parse_input_line(input: &str) -> (char, u32) {
-    let (raw_dir, raw_clicks) = input.split_at(1);
-    let direction = raw_dir.chars().last().expect("Missing direction");
-    let clicks: u32 = raw_clicks
+impl Default for Dial {
+    /// The puzzle's safe: 100 positions, starting at 50, counting zeroes
+    fn default() -> Dial {
+        Dial::new(100, 50, 0)
+    }
+}
+
+/// Turns a standard 100-position dial from `start` and returns the new position and
+/// how many clicks landed on 0
+pub fn rotate(start: u32, clicks: u64, direction: char) -> (u32, u64) {
+    let mut dial = Dial::new(100, start, 0);
+    let zeroes = dial.rotate(clicks, direction);
+    (dial.position(), zeroes)
+}
+
+/// What went wrong with an instruction line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InstructionErrorKind {
+    Empty,
+    UnknownDirection,
+    MissingClicks,
+    InvalidClicks,
+    ClicksOverflow,
+    Unreadable,
+    UnexpectedToken,
+    UnclosedGroup,
+    InvalidRepeat,
+    MissingWheel,
+    UnknownWheel,
+}
+
+/// A bad instruction, located by 1-based line and column
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InstructionError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: InstructionErrorKind,
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            InstructionErrorKind::Empty => "empty instruction",
+            InstructionErrorKind::UnknownDirection => "unknown direction",
+            InstructionErrorKind::MissingClicks => "missing click count",
+            InstructionErrorKind::InvalidClicks => "click count is not a number",
+            InstructionErrorKind::ClicksOverflow => "click count is too large",
+            InstructionErrorKind::Unreadable => "unable to read line",
+            InstructionErrorKind::UnexpectedToken => "unexpected",
+            InstructionErrorKind::UnclosedGroup => "group is never closed",
+            InstructionErrorKind::InvalidRepeat => "invalid repeat count",
+            InstructionErrorKind::MissingWheel => "missing wheel name",
+            InstructionErrorKind::UnknownWheel => "no wheel named",
+        };
+        write!(
+            f,
+            "line {}, column {}: {reason} {:?}",
+            self.line, self.column, self.text
+        )
+    }
+}
+
+impl Error for InstructionError {}
+
+/// Parses a line like "L68" into its direction and click count
+///
+/// # Examples
+///
+/// ```
+/// let result = day01::parse_input_line("R48");
+/// assert_eq!(result, Ok(('R', 48)));
+/// ```
+/// ```
+/// let error = day01::parse_input_line("X48").unwrap_err();
+/// assert_eq!(error.kind, day01::InstructionErrorKind::UnknownDirection);
+/// assert_eq!(error.column, 1);
+/// ```
+pub fn parse_input_line(input: &str) -> Result<(char, u64), InstructionError> {
+    parse_numbered_line(1, input)
+}
+
+pub(crate) fn parse_numbered_line(
+    line: usize,
+    input: &str,
+) -> Result<(char, u64), InstructionError> {
+    let error = |column: usize, text: &str, kind: InstructionErrorKind| InstructionError {
+        line,
+        column,
+        text: text.to_string(),
+        kind,
+    };
+    let trimmed = input.trim_end();
+    let mut chars = trimmed.chars();
+    let direction = match chars.next() {
+        None => return Err(error(1, input, InstructionErrorKind::Empty)),
+        Some(d) if d == 'L' || d == 'R' => d,
+        Some(d) => {
+            return Err(error(
+                1,
+                &d.to_string(),
+                InstructionErrorKind::UnknownDirection,
+            ));
+        }
+    };
+    let raw_clicks = chars.as_str();
+    if raw_clicks.is_empty() {
+        return Err(error(2, raw_clicks, InstructionErrorKind::MissingClicks));
+    }
+    if !raw_clicks.chars().all(|c| c.is_ascii_digit()) {
+        return Err(error(2, raw_clicks, InstructionErrorKind::InvalidClicks));
+    }
+    let clicks: u64 = raw_clicks
         .parse()
-        .expect("unable to parse clicks from line");
-    (direction, clicks)
+        .map_err(|_| error(2, raw_clicks, InstructionErrorKind::ClicksOverflow))?;
+    Ok((direction, clicks))
 }
 
-pub fn check_safe(input: File) -> (u32, u32) {
-    let mut dial: u32 = 50;
-    let mut rests_at_zero: u32 = 0;
-    let mut extra_zeroes: u32;
+pub(crate) fn parse_lines(
+    input: File,
+) -> impl Iterator<Item = Result<(char, u64), InstructionError>> {
     let buf = BufReader::new(input);
-    for line in buf.lines() {
-        let (direction, clicks) = parse_input_line(&line.expect("weird line"));
-        (dial, extra_zeroes) = rotate(dial, clicks, direction);
-        rests_at_zero += extra_zeroes;
+    buf.lines().enumerate().map(|(i, line)| match line {
+        Ok(text) => parse_numbered_line(i + 1, &text),
+        Err(e) => Err(unreadable_line(i + 1, e)),
+    })
+}
+
+pub(crate) fn unreadable_line(line: usize, e: io::Error) -> InstructionError {
+    InstructionError {
+        line,
+        column: 1,
+        text: e.to_string(),
+        kind: InstructionErrorKind::Unreadable,
+    }
+}
+
+// Hands the file's instructions to `run` as a stream that ends at the first bad line, and
+// returns that line's error in place of the result if there was one
+pub(crate) fn run_until_error<T>(
+    input: File,
+    run: impl FnOnce(&mut dyn Iterator<Item = (char, u64)>) -> T,
+) -> Result<T, InstructionError> {
+    let mut first_error: Option<InstructionError> = None;
+    let mut instructions = parse_lines(input).map_while(|parsed| match parsed {
+        Ok(instruction) => Some(instruction),
+        Err(e) => {
+            first_error = Some(e);
+            None
+        }
+    });
+    let result = run(&mut instructions);
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(result),
     }
+}
 
-    (rests_at_zero, dial)
+/// Runs every instruction in the file through the default dial,
+/// stopping at the first bad line
+pub fn check_safe(input: File) -> Result<SafeReport, InstructionError> {
+    let mut dial = Dial::default();
+    run_until_error(input, |instructions| dial.run(instructions))
+}
+
+/// Like check_safe, but skips bad lines and reports every one of them
+pub fn check_safe_collect_errors(input: File) -> Result<SafeReport, Vec<InstructionError>> {
+    let (report, errors) = run_skipping_errors(input);
+    if errors.is_empty() {
+        Ok(report)
+    } else {
+        Err(errors)
+    }
+}
+
+// runs the good lines on the default dial and hands back the bad ones alongside
+pub(crate) fn run_skipping_errors(input: File) -> (SafeReport, Vec<InstructionError>) {
+    let mut dial = Dial::default();
+    let mut errors: Vec<InstructionError> = Vec::new();
+    let instructions = parse_lines(input).filter_map(|parsed| match parsed {
+        Ok(instruction) => Some(instruction),
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    });
+    let report = dial.run(instructions);
+    (report, errors)
+}
+
+/// Works out where the standard dial started from where it ended up, returning the
+/// starting position and the report of the forward run
+pub fn rewind_safe(
+    final_position: u32,
+    input: File,
+) -> Result<(u32, SafeReport), InstructionError> {
+    let instructions: Vec<(char, u64)> = parse_lines(input).collect::<Result<_, _>>()?;
+    let mut dial = Dial::new(100, final_position, 0);
+    let report = dial.rewind(&instructions);
+    Ok((dial.position(), report))
 }
 
 #[cfg(test)]
@@ -97,6 +472,83 @@ mod tests {
         assert_eq!(result, (95, 101));
     }
 
+    #[test]
+    fn rotate_huge_click_counts_work() {
+        let result = rotate(50, 4_000_000_000, 'R');
+        assert_eq!(result, (50, 40_000_000));
+        let result = rotate(50, 4_000_000_050, 'L');
+        assert_eq!(result, (0, 40_000_001));
+    }
+
+    #[test]
+    fn rotate_from_zero_works() {
+        assert_eq!(rotate(0, 5, 'L'), (95, 0));
+        assert_eq!(rotate(0, 100, 'L'), (0, 1));
+        assert_eq!(rotate(0, 100, 'R'), (0, 1));
+    }
+
+    #[test]
+    fn rotate_max_clicks_does_not_overflow() {
+        assert_eq!(rotate(99, u64::MAX, 'R'), (14, 184467440737095517));
+        assert_eq!(rotate(0, u64::MAX, 'L'), (85, 184467440737095516));
+    }
+
+    #[test]
+    fn rotate_zero_clicks_stays_put() {
+        assert_eq!(rotate(0, 0, 'R'), (0, 0));
+        assert_eq!(rotate(42, 0, 'L'), (42, 0));
+    }
+
+    #[test]
+    fn dial_counts_custom_mark() {
+        let mut dial = Dial::new(40, 10, 25);
+        assert_eq!(dial.rotate(15, 'R'), 1);
+        assert_eq!(dial.rotate(80, 'L'), 2);
+        assert_eq!(dial.position(), 25);
+        assert_eq!(dial.rotate(40, 'R'), 1);
+    }
+
+    #[test]
+    fn dial_reset_returns_to_start() {
+        let mut dial = Dial::new(7, 9, 0);
+        assert_eq!(dial.start(), 2);
+        dial.rotate(3, 'L');
+        assert_eq!(dial.position(), 6);
+        dial.reset();
+        assert_eq!(dial.position(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dial_panics_on_zero_size() {
+        Dial::new(0, 0, 0);
+    }
+
+    #[test]
+    fn dial_undo_matches_rotate() {
+        for start in [0, 1, 37, 99] {
+            for clicks in [0, 1, 37, 99, 100, 101, 250] {
+                for direction in ['L', 'R'] {
+                    let mut dial = Dial::new(100, start, 0);
+                    let hits = dial.rotate(clicks, direction);
+                    assert_eq!(dial.undo(clicks, direction), hits);
+                    assert_eq!(dial.position(), start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rewind_safe_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
+        let data = File::open(path).expect("test1.txt file missing");
+        let (start, report) = rewind_safe(32, data).expect("bad instruction");
+        assert_eq!(start, 50);
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
+        let data = File::open(path).expect("test1.txt file missing");
+        assert_eq!(Ok(report), check_safe(data));
+    }
+
     #[test]
     #[should_panic]
     fn rotate_panics_on_weird_direction() {
@@ -106,36 +558,88 @@ mod tests {
     #[test]
     fn parse_input_line_handles_left() {
         let result = parse_input_line("L2000");
-        assert_eq!(result, ('L', 2000));
+        assert_eq!(result, Ok(('L', 2000)));
     }
 
     #[test]
     fn parse_input_line_handles_right() {
         let result = parse_input_line("R15");
-        assert_eq!(result, ('R', 15));
+        assert_eq!(result, Ok(('R', 15)));
+    }
+
+    #[test]
+    fn parse_input_line_rejects_bad_lines() {
+        let kind = |input: &str| parse_input_line(input).unwrap_err().kind;
+        assert_eq!(kind(""), InstructionErrorKind::Empty);
+        assert_eq!(kind("Q30"), InstructionErrorKind::UnknownDirection);
+        assert_eq!(kind("R"), InstructionErrorKind::MissingClicks);
+        assert_eq!(kind("L3O"), InstructionErrorKind::InvalidClicks);
+        assert_eq!(kind("R-5"), InstructionErrorKind::InvalidClicks);
+        assert_eq!(
+            kind("R99999999999999999999999"),
+            InstructionErrorKind::ClicksOverflow
+        );
+    }
+
+    #[test]
+    fn parse_input_line_handles_zero_clicks() {
+        assert_eq!(parse_input_line("R0"), Ok(('R', 0)));
+    }
+
+    #[test]
+    fn check_safe_stops_at_first_bad_line() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test3.txt");
+        let data = File::open(path).expect("test3.txt file missing");
+        let error = check_safe(data).unwrap_err();
+        let expected = InstructionError {
+            line: 3,
+            column: 2,
+            text: String::from("1O"),
+            kind: InstructionErrorKind::InvalidClicks,
+        };
+        assert_eq!(error, expected);
+    }
+
+    #[test]
+    fn check_safe_collect_errors_finds_every_bad_line() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test3.txt");
+        let data = File::open(path).expect("test3.txt file missing");
+        let errors = check_safe_collect_errors(data).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [3, 5, 6]);
+        assert_eq!(errors[1].kind, InstructionErrorKind::Empty);
+        assert_eq!(errors[2].kind, InstructionErrorKind::UnknownDirection);
     }
 
     #[test]
     fn check_safe_example_works() {
         let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
         let data = File::open(path).expect("test1.txt file missing");
-        let result = check_safe(data);
-        assert_eq!(result, (6, 32));
+        let result = check_safe(data).expect("bad instruction");
+        let expected = SafeReport {
+            rests_at_zero: 3,
+            passes_zero: 6,
+            final_position: 32,
+            instructions: 10,
+        };
+        assert_eq!(result, expected);
     }
 
     #[test]
     fn check_safe_example_2_works() {
         let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
         let data = File::open(path).expect("test1.txt file missing");
-        let result = check_safe(data);
-        assert_eq!(result, (10, 50));
+        let result = check_safe(data).expect("bad instruction");
+        assert_eq!(result.passes_zero, 10);
+        assert_eq!(result.final_position, 50);
     }
 
     #[test]
     fn check_safe_real_works() {
         let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/input.txt");
         let data = File::open(path).expect("test1.txt file missing");
-        let result = check_safe(data);
-        assert_eq!(result, (5963, 97));
+        let result = check_safe(data).expect("bad instruction");
+        assert_eq!(result.passes_zero, 5963);
+        assert_eq!(result.final_position, 97);
     }
 }
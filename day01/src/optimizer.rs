@@ -0,0 +1,212 @@
+use crate::Dial;
+
+/// How closely a simplified program has to match the original
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Equivalence {
+    /// ends on the same position from any start
+    FinalPosition,
+    /// ends on the same position and lands on the mark as many times as the original,
+    /// when started from the given dial
+    FinalPositionAndZeroes,
+}
+
+/// A simplified program along with how much smaller it is than the original
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Simplification {
+    pub instructions: Vec<(char, u64)>,
+    pub original_instructions: usize,
+    pub original_clicks: u128,
+    pub clicks: u128,
+}
+
+impl Simplification {
+    pub fn instructions_removed(&self) -> usize {
+        self.original_instructions - self.instructions.len()
+    }
+
+    /// Negative when fewer instructions needed extra full turns to keep the zero count
+    pub fn clicks_removed(&self) -> i128 {
+        self.original_clicks as i128 - self.clicks as i128
+    }
+}
+
+/// Folds a program into the shortest equivalent one.
+///
+/// Only the net turn matters for the final position, so a whole program collapses to at
+/// most one move. Keeping the zero count too means adding back full turns, which each
+/// land on the mark exactly once. That can take more clicks than the original program
+/// when it bounced off the mark, but never more instructions; a program that can't get
+/// any shorter comes back unchanged.
+///
+/// # Examples
+///
+/// ```
+/// let dial = day01::Dial::default();
+/// let program = [('R', 30), ('L', 30), ('R', 100)];
+/// let simpler = day01::simplify(&dial, &program, day01::Equivalence::FinalPosition);
+/// assert!(simpler.instructions.is_empty());
+/// assert_eq!(simpler.instructions_removed(), 3);
+///
+/// let simpler = day01::simplify(&dial, &program, day01::Equivalence::FinalPositionAndZeroes);
+/// assert_eq!(simpler.instructions, [('R', 100)]);
+/// assert_eq!(simpler.clicks_removed(), 60);
+/// ```
+pub fn simplify(dial: &Dial, instructions: &[(char, u64)], level: Equivalence) -> Simplification {
+    let size = u128::from(dial.size());
+    let mut original = *dial;
+    let mut zeroes: u128 = 0;
+    let mut original_clicks: u128 = 0;
+    for &(direction, clicks) in instructions {
+        zeroes += u128::from(original.rotate(clicks, direction));
+        original_clicks += u128::from(clicks);
+    }
+
+    let start = u128::from(dial.position());
+    let end = u128::from(original.position());
+    let right = (end + size - start) % size;
+    let left = (start + size - end) % size;
+
+    let simplified = match level {
+        Equivalence::FinalPosition => {
+            if right == 0 {
+                Vec::new()
+            } else if right <= left {
+                vec![('R', right as u64)]
+            } else {
+                vec![('L', left as u64)]
+            }
+        }
+        Equivalence::FinalPositionAndZeroes => {
+            if right == 0 && zeroes == 0 {
+                Vec::new()
+            } else {
+                // the shortest way round in either direction, topped up with full turns
+                //   until it hits the mark as often as the original did
+                let candidates = [('R', right), ('L', left)];
+                let (direction, clicks) = candidates
+                    .into_iter()
+                    .filter_map(|(direction, base)| {
+                        let mut trial = *dial;
+                        let hits = u128::from(trial.rotate(base as u64, direction));
+                        (hits <= zeroes).then(|| (direction, base + (zeroes - hits) * size))
+                    })
+                    .min_by_key(|&(_, clicks)| clicks)
+                    .expect("one way round always passes the mark no more than the original");
+                split_clicks(direction, clicks, size)
+            }
+        }
+    };
+
+    let simplified = if simplified.len() < instructions.len() {
+        simplified
+    } else {
+        instructions.to_vec()
+    };
+    Simplification {
+        clicks: simplified.iter().map(|&(_, c)| u128::from(c)).sum(),
+        instructions: simplified,
+        original_instructions: instructions.len(),
+        original_clicks,
+    }
+}
+
+// a single instruction can't hold more than u64::MAX clicks, so hand out whole turns in
+//   the biggest chunks that fit, which leaves the position and mark hits unchanged
+fn split_clicks(direction: char, clicks: u128, size: u128) -> Vec<(char, u64)> {
+    let chunk = u128::from(u64::MAX) / size * size;
+    let mut remaining = clicks;
+    let mut result = Vec::new();
+    while remaining > u128::from(u64::MAX) {
+        result.push((direction, chunk as u64));
+        remaining -= chunk;
+    }
+    result.push((direction, remaining as u64));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_safe, parse_lines};
+    use std::fs::File;
+    use std::path::Path;
+
+    #[test]
+    fn simplify_example_keeps_check_safe_answer() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(&path).expect("test2.txt file missing");
+        let instructions: Vec<(char, u64)> = parse_lines(data).map(|i| i.unwrap()).collect();
+        let report = check_safe(File::open(&path).expect("test2.txt file missing")).unwrap();
+
+        let simpler = simplify(
+            &Dial::default(),
+            &instructions,
+            Equivalence::FinalPositionAndZeroes,
+        );
+        let mut dial = Dial::default();
+        let simpler_report = dial.run(simpler.instructions.clone());
+        assert_eq!(simpler_report.passes_zero, report.passes_zero);
+        assert_eq!(simpler_report.final_position, report.final_position);
+        assert_eq!(simpler.instructions.len(), 1);
+        assert_eq!(simpler.instructions_removed(), instructions.len() - 1);
+    }
+
+    #[test]
+    fn simplify_final_position_picks_shorter_way() {
+        let dial = Dial::default();
+        let result = simplify(&dial, &[('R', 230), ('L', 5)], Equivalence::FinalPosition);
+        assert_eq!(result.instructions, [('R', 25)]);
+        assert_eq!(result.original_clicks, 235);
+        assert_eq!(result.clicks_removed(), 210);
+        let result = simplify(
+            &dial,
+            &[('R', 30), ('L', 5), ('R', 50)],
+            Equivalence::FinalPosition,
+        );
+        assert_eq!(result.instructions, [('L', 25)]);
+    }
+
+    #[test]
+    fn simplify_keeps_zeroes_from_every_start() {
+        let program = [('L', 30), ('R', 75), ('L', 120), ('R', 0), ('R', 99)];
+        for start in 0..100 {
+            let dial = Dial::new(100, start, 0);
+            let mut original = dial;
+            let expected = original.run(program);
+            let simpler = simplify(&dial, &program, Equivalence::FinalPositionAndZeroes);
+            let mut replay = dial;
+            let report = replay.run(simpler.instructions.clone());
+            assert_eq!(report.passes_zero, expected.passes_zero);
+            assert_eq!(report.final_position, expected.final_position);
+            assert!(simpler.instructions.len() <= 1);
+        }
+    }
+
+    #[test]
+    fn simplify_may_add_turns_but_never_instructions() {
+        let dial = Dial::new(100, 1, 0);
+        let program = [('L', 1), ('R', 1)];
+        let simpler = simplify(&dial, &program, Equivalence::FinalPositionAndZeroes);
+        assert_eq!(simpler.instructions, [('R', 100)]);
+        assert_eq!(simpler.clicks_removed(), -98);
+
+        let single = [('L', 201)];
+        let simpler = simplify(&dial, &single, Equivalence::FinalPositionAndZeroes);
+        assert_eq!(simpler.instructions, single);
+    }
+
+    #[test]
+    fn simplify_splits_more_clicks_than_fit_in_one_instruction() {
+        let dial = Dial::default();
+        let program = [('R', u64::MAX), ('R', u64::MAX)];
+        let simpler = simplify(&dial, &program, Equivalence::FinalPositionAndZeroes);
+        assert_eq!(simpler.instructions.len(), 2);
+        let mut original = dial;
+        let mut replay = dial;
+        assert_eq!(
+            replay.apply(simpler.instructions.clone()),
+            original.apply(program)
+        );
+        assert_eq!(replay.position(), original.position());
+    }
+}
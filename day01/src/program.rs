@@ -0,0 +1,381 @@
+use crate::{Dial, InstructionError, InstructionErrorKind, SafeReport};
+use std::fs::File;
+use std::io::Read;
+
+/// A piece of a parsed lock program
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Step {
+    Move { direction: char, clicks: u64 },
+    Repeat { body: Vec<Step>, times: u64 },
+}
+
+/// A lock program in the extended instruction syntax.
+///
+/// On top of the plain `L68` lines this accepts
+/// - `#` comments and blank lines
+/// - whitespace anywhere between tokens, so `L 30` and several moves per line
+/// - signed moves, `-30` turns left and `+30` turns right
+/// - repeats of a single move `R30x5` or of a group `(L10 R20)*3`, either `x` or `*` works
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Program {
+    pub steps: Vec<Step>,
+}
+
+impl Program {
+    /// Expands the repeats lazily into the `(direction, clicks)` stream the dial consumes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let program = day01::parse_program("(L10 +20)*2 # twice\nR5x3").unwrap();
+    /// let expanded: Vec<(char, u64)> = program.instructions().collect();
+    /// assert_eq!(
+    ///     expanded,
+    ///     [('L', 10), ('R', 20), ('L', 10), ('R', 20), ('R', 5), ('R', 5), ('R', 5)]
+    /// );
+    /// ```
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions {
+            stack: vec![(&self.steps, 0, 1)],
+        }
+    }
+}
+
+/// Iterator over the expanded instructions of a Program
+pub struct Instructions<'a> {
+    // steps being walked, index of the next step, passes left including this one
+    stack: Vec<(&'a [Step], usize, u64)>,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = (char, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (steps, index, remaining) = self.stack.last_mut()?;
+            if *index == steps.len() {
+                if *remaining > 1 {
+                    *remaining -= 1;
+                    *index = 0;
+                } else {
+                    self.stack.pop();
+                }
+                continue;
+            }
+            let step = &steps[*index];
+            *index += 1;
+            match step {
+                Step::Move { direction, clicks } => return Some((*direction, *clicks)),
+                Step::Repeat { body, times } => {
+                    if *times > 0 && yields_anything(body) {
+                        self.stack.push((body, 0, *times));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// true if walking the steps would hand out at least one move. Programs can be built by hand,
+// so a repeat of nothing but empty repeats has to be caught here and not just by the parser.
+fn yields_anything(steps: &[Step]) -> bool {
+    steps.iter().any(|step| match step {
+        Step::Move { .. } => true,
+        Step::Repeat { body, times } => *times > 0 && yields_anything(body),
+    })
+}
+
+/// Parses a lock program written in the extended syntax
+///
+/// # Examples
+///
+/// ```
+/// let program = day01::parse_program("L 68\n-30\n\n# done").unwrap();
+/// assert_eq!(program.instructions().collect::<Vec<_>>(), [('L', 68), ('L', 30)]);
+/// ```
+/// ```
+/// let error = day01::parse_program("R10\n(L5 R5").unwrap_err();
+/// assert_eq!(error.kind, day01::InstructionErrorKind::UnclosedGroup);
+/// assert_eq!((error.line, error.column), (2, 1));
+/// ```
+pub fn parse_program(input: &str) -> Result<Program, InstructionError> {
+    let mut parser = Parser::new(input);
+    let steps = parser.items(None)?;
+    Ok(Program { steps })
+}
+
+/// Runs a file written in the extended syntax through the default dial
+pub fn check_safe_program(mut input: File) -> Result<SafeReport, InstructionError> {
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| crate::unreadable_line(1, e))?;
+    let program = parse_program(&text)?;
+    let mut dial = Dial::default();
+    Ok(dial.run(program.instructions()))
+}
+
+// Repeats that expand to nothing are dropped, so walking the program never loops over
+// an empty body, however many times it was meant to repeat
+fn push_repeat(steps: &mut Vec<Step>, body: Vec<Step>, times: u64) {
+    if times > 0 && !body.is_empty() {
+        steps.push(Step::Repeat { body, times });
+    }
+}
+
+struct Parser {
+    // every character along with its 1-based line and column
+    chars: Vec<(usize, usize, char)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Parser {
+        let mut chars = Vec::new();
+        let (mut line, mut column) = (1, 1);
+        for c in input.chars() {
+            chars.push((line, column, c));
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        Parser { chars, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, _, c)| c)
+    }
+
+    // where the next character is, or just past the end of the input
+    fn location(&self) -> (usize, usize) {
+        match self.chars.get(self.pos) {
+            Some(&(line, column, _)) => (line, column),
+            None => match self.chars.last() {
+                Some(&(line, _, '\n')) => (line + 1, 1),
+                Some(&(line, column, _)) => (line, column + 1),
+                None => (1, 1),
+            },
+        }
+    }
+
+    fn error(
+        &self,
+        at: (usize, usize),
+        text: &str,
+        kind: InstructionErrorKind,
+    ) -> InstructionError {
+        InstructionError {
+            line: at.0,
+            column: at.1,
+            text: text.to_string(),
+            kind,
+        }
+    }
+
+    // skips spaces and tabs but stays on the current line
+    fn skip_inline_space(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t') | Some('\r')) {
+            self.pos += 1;
+        }
+    }
+
+    // skips all whitespace, newlines and comments
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while !matches!(self.peek(), None | Some('\n')) {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    // parses steps until the end of input, or until the matching ')' when inside a group
+    fn items(
+        &mut self,
+        group_start: Option<(usize, usize)>,
+    ) -> Result<Vec<Step>, InstructionError> {
+        let mut steps = Vec::new();
+        loop {
+            self.skip_space();
+            let at = self.location();
+            match self.peek() {
+                None => {
+                    return match group_start {
+                        Some(start) => {
+                            Err(self.error(start, "(", InstructionErrorKind::UnclosedGroup))
+                        }
+                        None => Ok(steps),
+                    };
+                }
+                Some(')') if group_start.is_some() => {
+                    self.pos += 1;
+                    return Ok(steps);
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let body = self.items(Some(at))?;
+                    let times = self.repeat()?.unwrap_or(1);
+                    push_repeat(&mut steps, body, times);
+                }
+                Some(c) if matches!(c, 'L' | 'R' | '+' | '-') => {
+                    self.pos += 1;
+                    let direction = if c == 'L' || c == '-' { 'L' } else { 'R' };
+                    self.skip_inline_space();
+                    let clicks = self.number(
+                        InstructionErrorKind::MissingClicks,
+                        InstructionErrorKind::ClicksOverflow,
+                    )?;
+                    let step = Step::Move { direction, clicks };
+                    match self.repeat()? {
+                        Some(times) => push_repeat(&mut steps, vec![step], times),
+                        None => steps.push(step),
+                    }
+                }
+                Some(c) => {
+                    return Err(self.error(
+                        at,
+                        &c.to_string(),
+                        InstructionErrorKind::UnexpectedToken,
+                    ));
+                }
+            }
+        }
+    }
+
+    // parses an optional `x5` or `*5` suffix
+    fn repeat(&mut self) -> Result<Option<u64>, InstructionError> {
+        let before = self.pos;
+        self.skip_inline_space();
+        if !matches!(self.peek(), Some('x') | Some('*')) {
+            self.pos = before;
+            return Ok(None);
+        }
+        self.pos += 1;
+        self.skip_inline_space();
+        let times = self.number(
+            InstructionErrorKind::InvalidRepeat,
+            InstructionErrorKind::InvalidRepeat,
+        )?;
+        Ok(Some(times))
+    }
+
+    fn number(
+        &mut self,
+        missing: InstructionErrorKind,
+        overflow: InstructionErrorKind,
+    ) -> Result<u64, InstructionError> {
+        let at = self.location();
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(c);
+            self.pos += 1;
+        }
+        if digits.is_empty() {
+            let found = self.peek().map(|c| c.to_string()).unwrap_or_default();
+            return Err(self.error(at, &found, missing));
+        }
+        digits
+            .parse()
+            .map_err(|_| self.error(at, &digits, overflow))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn check_safe_program_matches_plain_example() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test4.txt");
+        let data = File::open(path).expect("test4.txt file missing");
+        let result = check_safe_program(data).expect("bad program");
+        let expected = SafeReport {
+            rests_at_zero: 3,
+            passes_zero: 6,
+            final_position: 32,
+            instructions: 10,
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn nested_repeats_expand() {
+        let program = parse_program("((R1)x2 L1)*2 R0x0").unwrap();
+        let expanded: Vec<(char, u64)> = program.instructions().collect();
+        assert_eq!(
+            expanded,
+            [('R', 1), ('R', 1), ('L', 1), ('R', 1), ('R', 1), ('L', 1)]
+        );
+    }
+
+    #[test]
+    fn huge_repeats_stay_lazy() {
+        let program = parse_program("R1x18446744073709551615").unwrap();
+        assert_eq!(program.instructions().take(3).count(), 3);
+    }
+
+    #[test]
+    fn empty_repeats_are_dropped() {
+        let program = parse_program("(R0x0)x18446744073709551615").unwrap();
+        assert_eq!(program.steps, []);
+        assert_eq!(program.instructions().next(), None);
+        let program = parse_program("(()x5 (L1x0))x18446744073709551615 R2").unwrap();
+        assert_eq!(program.instructions().collect::<Vec<_>>(), [('R', 2)]);
+    }
+
+    #[test]
+    fn hand_built_empty_repeats_end() {
+        let empty = Step::Repeat {
+            body: vec![Step::Repeat {
+                body: vec![],
+                times: 1,
+            }],
+            times: u64::MAX,
+        };
+        let program = Program {
+            steps: vec![
+                empty.clone(),
+                Step::Move {
+                    direction: 'L',
+                    clicks: 3,
+                },
+                empty,
+            ],
+        };
+        assert_eq!(program.instructions().collect::<Vec<_>>(), [('L', 3)]);
+    }
+
+    #[test]
+    fn bad_programs_report_location() {
+        let check = |input: &str, line: usize, column: usize, kind: InstructionErrorKind| {
+            let error = parse_program(input).unwrap_err();
+            assert_eq!((error.line, error.column, error.kind), (line, column, kind));
+        };
+        check("R10\nQ5", 2, 1, InstructionErrorKind::UnexpectedToken);
+        check("L", 1, 2, InstructionErrorKind::MissingClicks);
+        check("R5 L", 1, 5, InstructionErrorKind::MissingClicks);
+        check("R5x", 1, 4, InstructionErrorKind::InvalidRepeat);
+        check("R10)", 1, 4, InstructionErrorKind::UnexpectedToken);
+        check(
+            "R99999999999999999999",
+            1,
+            2,
+            InstructionErrorKind::ClicksOverflow,
+        );
+        check(
+            "# fine\n  (R1 (L2)",
+            2,
+            3,
+            InstructionErrorKind::UnclosedGroup,
+        );
+    }
+}
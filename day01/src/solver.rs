@@ -0,0 +1,118 @@
+use crate::Dial;
+
+/// Whether each move of a solved program may land on the dial's mark on the way
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PassRule {
+    #[default]
+    Any,
+    MustPass,
+    MustNotPass,
+}
+
+/// Finds the program with the fewest total clicks that rests on each target in order,
+/// starting from the dial's current position.
+///
+/// Every target gets exactly one instruction, and the pass rule is applied to each of
+/// them (resting on the mark counts as passing it). Returns None when some target
+/// can't be reached under the rule, e.g. resting on the mark while it must not be passed.
+///
+/// # Examples
+///
+/// ```
+/// let dial = day01::Dial::default();
+/// let program = day01::solve_combination(&dial, &[20, 90], day01::PassRule::Any);
+/// assert_eq!(program, Some(vec![('L', 30), ('L', 30)]));
+/// ```
+/// ```
+/// let dial = day01::Dial::default();
+/// let program = day01::solve_combination(&dial, &[20, 90], day01::PassRule::MustNotPass);
+/// assert_eq!(program, Some(vec![('L', 30), ('R', 70)]));
+/// ```
+pub fn solve_combination(dial: &Dial, targets: &[u32], rule: PassRule) -> Option<Vec<(char, u64)>> {
+    let size = u64::from(dial.size());
+    let mut current = *dial;
+    let mut program: Vec<(char, u64)> = Vec::new();
+
+    for &target in targets {
+        // in u64 so sizes near u32::MAX can't overflow
+        let target = u64::from(target) % size;
+        let position = u64::from(current.position());
+        let right = (target + size - position) % size;
+        let left = (position + size - target) % size;
+        // legs don't affect each other since every one ends on its target, so the
+        //   cheapest allowed move for each leg gives the cheapest program.
+        // an extra full turn always passes the mark, so nothing longer is ever needed
+        let candidates = [
+            ('R', right),
+            ('L', left),
+            ('R', right + size),
+            ('L', left + size),
+        ];
+        let best = candidates
+            .into_iter()
+            .filter(|&(direction, clicks)| {
+                let mut trial = current;
+                let hits = trial.rotate(clicks, direction);
+                match rule {
+                    PassRule::Any => true,
+                    PassRule::MustPass => hits > 0,
+                    PassRule::MustNotPass => hits == 0,
+                }
+            })
+            .min_by_key(|&(_, clicks)| clicks)?;
+        current.rotate(best.1, best.0);
+        program.push(best);
+    }
+    Some(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solved_programs_rest_on_every_target() {
+        let targets = [3, 97, 97, 0, 50, 49];
+        for rule in [PassRule::Any, PassRule::MustPass] {
+            let mut dial = Dial::default();
+            let program = solve_combination(&dial, &targets, rule).unwrap();
+            let mut rests: Vec<u32> = Vec::new();
+            for (direction, clicks) in program {
+                let hits = dial.rotate(clicks, direction);
+                if rule == PassRule::MustPass {
+                    assert!(hits > 0);
+                }
+                rests.push(dial.position());
+            }
+            assert_eq!(rests, targets);
+        }
+    }
+
+    #[test]
+    fn must_pass_takes_the_long_way_when_needed() {
+        let dial = Dial::default();
+        let program = solve_combination(&dial, &[60, 60], PassRule::MustPass);
+        assert_eq!(program, Some(vec![('L', 90), ('R', 100)]));
+    }
+
+    #[test]
+    fn must_not_pass_cannot_rest_on_the_mark() {
+        let dial = Dial::default();
+        assert_eq!(
+            solve_combination(&dial, &[10, 0], PassRule::MustNotPass),
+            None
+        );
+        let dial = Dial::new(100, 0, 0);
+        assert_eq!(
+            solve_combination(&dial, &[0], PassRule::MustNotPass),
+            Some(vec![('R', 0)])
+        );
+    }
+
+    #[test]
+    fn huge_dials_do_not_overflow() {
+        let dial = Dial::new(u32::MAX, 5, 0);
+        let program = solve_combination(&dial, &[10, u32::MAX - 1], PassRule::Any);
+        assert_eq!(program, Some(vec![('R', 5), ('L', 11)]));
+    }
+}
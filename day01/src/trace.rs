@@ -0,0 +1,239 @@
+use crate::{Dial, InstructionError, SafeReport, parse_numbered_line, unreadable_line};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines, Write};
+
+/// One instruction's worth of dial movement, with the running totals after it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceStep {
+    pub line: usize,
+    pub input: String,
+    pub direction: char,
+    pub clicks: u64,
+    pub position_before: u32,
+    pub position_after: u32,
+    pub zeroes_passed: u64,
+    pub total_rests_at_zero: u64,
+    pub total_passes_zero: u64,
+}
+
+/// Iterator that replays an instruction stream on a dial, one step per line
+///
+/// # Examples
+///
+/// ```
+/// let input = "L68\nL30\nR48\n";
+/// let steps: Vec<day01::TraceStep> = day01::SafeRun::new(day01::Dial::default(), input.as_bytes())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(steps[0].position_after, 82);
+/// assert_eq!(steps[0].zeroes_passed, 1);
+/// assert_eq!(steps[2].position_before, 52);
+/// assert_eq!(steps[2].total_rests_at_zero, 1);
+/// assert_eq!(steps[2].total_passes_zero, 2);
+/// ```
+pub struct SafeRun<R: BufRead> {
+    dial: Dial,
+    lines: Lines<R>,
+    line: usize,
+    report: SafeReport,
+}
+
+impl<R: BufRead> SafeRun<R> {
+    pub fn new(dial: Dial, input: R) -> SafeRun<R> {
+        SafeRun {
+            dial,
+            lines: input.lines(),
+            line: 0,
+            report: SafeReport {
+                final_position: dial.position(),
+                ..SafeReport::default()
+            },
+        }
+    }
+
+    /// Totals for the instructions replayed so far
+    pub fn report(&self) -> SafeReport {
+        self.report
+    }
+
+    pub fn dial(&self) -> &Dial {
+        &self.dial
+    }
+}
+
+impl SafeRun<BufReader<File>> {
+    /// Replays a file on the puzzle's default dial
+    pub fn from_file(input: File) -> SafeRun<BufReader<File>> {
+        SafeRun::new(Dial::default(), BufReader::new(input))
+    }
+}
+
+impl<R: BufRead> Iterator for SafeRun<R> {
+    type Item = Result<TraceStep, InstructionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        self.line += 1;
+        let input = match line {
+            Ok(text) => text,
+            Err(e) => return Some(Err(unreadable_line(self.line, e))),
+        };
+        let (direction, clicks) = match parse_numbered_line(self.line, &input) {
+            Ok(instruction) => instruction,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let position_before = self.dial.position();
+        let zeroes_passed = self.dial.rotate(clicks, direction);
+        let position_after = self.dial.position();
+        self.report.record(&self.dial, zeroes_passed);
+
+        Some(Ok(TraceStep {
+            line: self.line,
+            input,
+            direction,
+            clicks,
+            position_before,
+            position_after,
+            zeroes_passed,
+            total_rests_at_zero: self.report.rests_at_zero,
+            total_passes_zero: self.report.passes_zero,
+        }))
+    }
+}
+
+const CSV_HEADER: &str = "line,input,direction,clicks,position_before,position_after,zeroes_passed,total_rests_at_zero,total_passes_zero";
+
+/// Writes trace steps as CSV with a header row
+///
+/// # Examples
+///
+/// ```
+/// let run = day01::SafeRun::new(day01::Dial::default(), "R60\n".as_bytes());
+/// let steps = run.map(|step| step.unwrap());
+/// let mut out: Vec<u8> = Vec::new();
+/// day01::write_csv_trace(&mut out, steps).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert_eq!(text.lines().nth(1), Some("1,R60,R,60,50,10,1,0,1"));
+/// ```
+pub fn write_csv_trace<W: Write, I: IntoIterator<Item = TraceStep>>(
+    out: &mut W,
+    steps: I,
+) -> io::Result<()> {
+    writeln!(out, "{CSV_HEADER}")?;
+    for step in steps {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            step.line,
+            csv_field(&step.input),
+            step.direction,
+            step.clicks,
+            step.position_before,
+            step.position_after,
+            step.zeroes_passed,
+            step.total_rests_at_zero,
+            step.total_passes_zero
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes trace steps as one JSON object per line
+///
+/// # Examples
+///
+/// ```
+/// let run = day01::SafeRun::new(day01::Dial::default(), "L50\n".as_bytes());
+/// let steps = run.map(|step| step.unwrap());
+/// let mut out: Vec<u8> = Vec::new();
+/// day01::write_json_lines_trace(&mut out, steps).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert!(text.starts_with(r#"{"line":1,"input":"L50","direction":"L","clicks":50,"#));
+/// ```
+pub fn write_json_lines_trace<W: Write, I: IntoIterator<Item = TraceStep>>(
+    out: &mut W,
+    steps: I,
+) -> io::Result<()> {
+    for step in steps {
+        writeln!(
+            out,
+            "{{\"line\":{},\"input\":{},\"direction\":\"{}\",\"clicks\":{},\"position_before\":{},\"position_after\":{},\"zeroes_passed\":{},\"total_rests_at_zero\":{},\"total_passes_zero\":{}}}",
+            step.line,
+            json_string(&step.input),
+            step.direction,
+            step.clicks,
+            step.position_before,
+            step.position_after,
+            step.zeroes_passed,
+            step.total_rests_at_zero,
+            step.total_passes_zero
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn safe_run_matches_check_safe() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(path).expect("test2.txt file missing");
+        let mut run = SafeRun::from_file(data);
+        let steps: Vec<TraceStep> = run.by_ref().map(|step| step.unwrap()).collect();
+        let last = steps.last().unwrap();
+        assert_eq!(last.total_passes_zero, 10);
+        assert_eq!(last.position_after, 50);
+        assert_eq!(run.report().instructions, steps.len() as u64);
+    }
+
+    #[test]
+    fn safe_run_reports_bad_lines_and_keeps_going() {
+        let mut run = SafeRun::new(Dial::default(), "R10\nX5\nL10\n".as_bytes());
+        assert!(run.next().unwrap().is_ok());
+        assert_eq!(run.next().unwrap().unwrap_err().line, 2);
+        let step = run.next().unwrap().unwrap();
+        assert_eq!(step.line, 3);
+        assert_eq!(step.position_after, 50);
+        assert!(run.next().is_none());
+    }
+
+    #[test]
+    fn csv_field_quotes_when_needed() {
+        assert_eq!(csv_field("R5"), "R5");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\u{1}"), "\"a\\\"b\\\\c\\u0001\"");
+    }
+}
@@ -0,0 +1,468 @@
+use crate::{RangeErrorKind, RangeParseError, RepetitionPolicy, divisors, mobius};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+// every limb holds nine decimal digits
+const LIMB: u64 = 1_000_000_000;
+
+/// A whole number of any size, used for IDs and range bounds too long for a u64 and for
+/// sums that could overflow any fixed width
+///
+/// # Examples
+///
+/// ```
+/// use day02::BigUint;
+/// let big: BigUint = "123456789012345678901234567890".parse().unwrap();
+/// let sum = &big + &BigUint::from(u128::MAX);
+/// assert_eq!(sum.to_string(), "340282367044395252475720286333002779345");
+/// assert_eq!(big.to_u128(), Some(123456789012345678901234567890));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BigUint {
+    // least significant first, with no zero limbs at the top, so zero has none at all
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Returns the number if it fits in a u128
+    pub fn to_u128(&self) -> Option<u128> {
+        let mut result: u128 = 0;
+        for &limb in self.limbs.iter().rev() {
+            result = result
+                .checked_mul(u128::from(LIMB))?
+                .checked_add(u128::from(limb))?;
+        }
+        Some(result)
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    fn mul_small(&self, factor: u32) -> BigUint {
+        let mut limbs: Vec<u32> = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry: u64 = 0;
+        for &limb in &self.limbs {
+            let product = u64::from(limb) * u64::from(factor) + carry;
+            limbs.push((product % LIMB) as u32);
+            carry = product / LIMB;
+        }
+        while carry > 0 {
+            limbs.push((carry % LIMB) as u32);
+            carry /= LIMB;
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    fn div_small(&self, divisor: u32) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut remainder: u64 = 0;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder * LIMB + u64::from(limb);
+            limbs[i] = (current / u64::from(divisor)) as u32;
+            remainder = current % u64::from(divisor);
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> BigUint {
+        BigUint::from(u128::from(value))
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> BigUint {
+        let mut limbs: Vec<u32> = Vec::new();
+        while value > 0 {
+            limbs.push((value % u128::from(LIMB)) as u32);
+            value /= u128::from(LIMB);
+        }
+        BigUint { limbs }
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = RangeParseError;
+
+    /// Parses plain decimal digits, leading zeros are allowed
+    fn from_str(text: &str) -> Result<BigUint, RangeParseError> {
+        if let Some((offset, c)) = text.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+            return Err(RangeParseError {
+                offset,
+                token: c.to_string(),
+                kind: RangeErrorKind::UnexpectedCharacter,
+            });
+        }
+        if text.is_empty() {
+            return Err(RangeParseError {
+                offset: 0,
+                token: String::new(),
+                kind: RangeErrorKind::MissingNumber,
+            });
+        }
+        let limbs = text
+            .as_bytes()
+            .rchunks(9)
+            .map(|chunk| {
+                let chunk = std::str::from_utf8(chunk).expect("digits are ascii");
+                chunk.parse().expect("nine digits fit in a limb")
+            })
+            .collect();
+        Ok(BigUint::from_limbs(limbs))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        match limbs.next() {
+            None => write!(f, "0"),
+            Some(top) => {
+                write!(f, "{top}")?;
+                for limb in limbs {
+                    write!(f, "{limb:09}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let length = self.limbs.len().max(other.limbs.len());
+        let mut limbs: Vec<u32> = Vec::with_capacity(length + 1);
+        let mut carry: u64 = 0;
+        for i in 0..length {
+            let a = u64::from(self.limbs.get(i).copied().unwrap_or(0));
+            let b = u64::from(other.limbs.get(i).copied().unwrap_or(0));
+            let total = a + b + carry;
+            limbs.push((total % LIMB) as u32);
+            carry = total / LIMB;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: BigUint) -> BigUint {
+        &self + &other
+    }
+}
+
+// there are no negative numbers, so taking away more than there is panics
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(*self >= *other, "subtracting {other} from {self}");
+        let mut limbs: Vec<u32> = Vec::with_capacity(self.limbs.len());
+        let mut borrow: i64 = 0;
+        for (i, &limb) in self.limbs.iter().enumerate() {
+            let b = i64::from(other.limbs.get(i).copied().unwrap_or(0));
+            let mut difference = i64::from(limb) - b - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += LIMB as i64;
+                borrow = 1;
+            }
+            limbs.push(difference as u32);
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len() + 1];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            //carry as we go, so no column grows past one limb squared
+            let mut carry: u64 = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let total = limbs[i + j] + u64::from(a) * u64::from(b) + carry;
+                limbs[i + j] = total % LIMB;
+                carry = total / LIMB;
+            }
+            limbs[i + other.limbs.len()] += carry;
+        }
+        BigUint::from_limbs(limbs.into_iter().map(|limb| limb as u32).collect())
+    }
+}
+
+/// Returns true if the id is not made up of the same substring repeated two or more
+/// times, for ids of any length
+///
+/// # Examples
+///
+/// ```
+/// use day02::BigUint;
+/// let id: BigUint = "12345678901234567890123456789012345678901234567890".parse().unwrap();
+/// assert!(!day02::is_valid_big_id(&id));
+/// assert!(day02::is_valid_big_id(&BigUint::from(u128::MAX)));
+/// ```
+pub fn is_valid_big_id(input: &BigUint) -> bool {
+    is_valid_big_id_with_policy(input, RepetitionPolicy::default())
+}
+
+/// Like is_valid_id_with_policy, for ids of any length
+///
+/// # Examples
+///
+/// ```
+/// use day02::{BigUint, RepetitionPolicy};
+/// let id: BigUint = "1234567890".repeat(4).parse().unwrap();
+/// assert!(day02::is_valid_big_id_with_policy(&id, RepetitionPolicy::Exactly(3)));
+/// assert!(!day02::is_valid_big_id_with_policy(&id, RepetitionPolicy::Exactly(4)));
+/// ```
+pub fn is_valid_big_id_with_policy(input: &BigUint, policy: RepetitionPolicy) -> bool {
+    let digits = input.to_string();
+    let digits = digits.as_bytes();
+    let length = digits.len() as u32;
+    //the first unit length the digits repeat with is the shortest unit
+    let unit_length = divisors(length)
+        .find(|&unit_length| {
+            let unit_length = unit_length as usize;
+            digits[unit_length..] == digits[..digits.len() - unit_length]
+        })
+        .expect("every id repeats itself once");
+    !policy.allows_divisor_of(length / unit_length)
+}
+
+/// Counts the invalid IDs in an inclusive range of any size under a policy, without
+/// visiting each ID
+///
+/// # Examples
+/// ```
+/// use day02::{BigUint, RepetitionPolicy};
+/// let start = BigUint::from(0u64);
+/// let end = BigUint::from(10u128.pow(24));
+/// let results = day02::count_invalid_big(&start, &end, RepetitionPolicy::Exactly(2));
+/// assert_eq!(results, BigUint::from(999_999_999_999u64));
+/// ```
+pub fn count_invalid_big(start: &BigUint, end: &BigUint, policy: RepetitionPolicy) -> BigUint {
+    let (count, _) = big_invalid_id_totals(start, end, policy);
+    count
+}
+
+/// Sums the invalid IDs in an inclusive range of any size under a policy, without
+/// visiting each ID
+///
+/// # Examples
+/// ```
+/// use day02::{BigUint, RepetitionPolicy};
+/// let start = BigUint::from(95u64);
+/// let end = BigUint::from(115u64);
+/// let results = day02::sum_invalid_big(&start, &end, RepetitionPolicy::default());
+/// assert_eq!(results, BigUint::from(210u64));
+/// ```
+/// ```
+/// use day02::{BigUint, RepetitionPolicy};
+/// let id: BigUint = "1".repeat(300).parse().unwrap();
+/// let results = day02::sum_invalid_big(&id, &id, RepetitionPolicy::default());
+/// assert_eq!(results, id);
+/// ```
+pub fn sum_invalid_big(start: &BigUint, end: &BigUint, policy: RepetitionPolicy) -> BigUint {
+    let (_, sum) = big_invalid_id_totals(start, end, policy);
+    sum
+}
+
+// invalid_id_totals for numbers of any size. The Möbius weights can be negative, so the
+// ids added and the ids taken away are kept apart until the end.
+fn big_invalid_id_totals(
+    start: &BigUint,
+    end: &BigUint,
+    policy: RepetitionPolicy,
+) -> (BigUint, BigUint) {
+    let mut added = (BigUint::default(), BigUint::default());
+    let mut removed = (BigUint::default(), BigUint::default());
+    if start > end {
+        return added;
+    }
+    let start_digits = start.to_string();
+    let end_digits = end.to_string();
+    for length in start_digits.len() as u32..=end_digits.len() as u32 {
+        for repeats in divisors(length) {
+            let weight: i32 = divisors(repeats)
+                .filter(|&q| policy.allows_divisor_of(q))
+                .map(|q| mobius(repeats / q))
+                .sum();
+            if weight == 0 {
+                continue;
+            }
+            let Some((first, last)) = big_unit_span(length, repeats, &start_digits, &end_digits)
+            else {
+                continue;
+            };
+            let units = &(&last - &first) + &BigUint::from(1u64);
+            let unit_sum = (&(&first + &last) * &units).div_small(2);
+            let unit_length = (length / repeats) as usize;
+            let repeater: BigUint = format!("{:0>unit_length$}", 1)
+                .repeat(repeats as usize)
+                .parse()
+                .expect("repeater is made of digits");
+            let sum = &unit_sum * &repeater;
+            let totals = if weight > 0 { &mut added } else { &mut removed };
+            let weight = weight.unsigned_abs();
+            totals.0 = &totals.0 + &units.mul_small(weight);
+            totals.1 = &totals.1 + &sum.mul_small(weight);
+        }
+    }
+    (&added.0 - &removed.0, &added.1 - &removed.1)
+}
+
+// The units of `length / repeats` digits whose repeats fall inside the range. The unit
+// nearest a bound of the same length is that bound's leading digits, moved by one when
+// repeating it lands outside the range.
+fn big_unit_span(length: u32, repeats: u32, start: &str, end: &str) -> Option<(BigUint, BigUint)> {
+    let unit_length = (length / repeats) as usize;
+    let length = length as usize;
+    let one = BigUint::from(1u64);
+    let smallest: BigUint = format!("1{}", "0".repeat(unit_length - 1))
+        .parse()
+        .expect("made of digits");
+    let largest: BigUint = "9".repeat(unit_length).parse().expect("made of digits");
+    let unit_of = |bound: &str| -> (BigUint, Ordering) {
+        let unit = &bound[..unit_length];
+        let repeated = unit.repeat(repeats as usize);
+        let unit = unit.parse().expect("made of digits");
+        (unit, repeated.as_str().cmp(bound))
+    };
+    let first = if start.len() < length {
+        smallest
+    } else {
+        match unit_of(start) {
+            (unit, Ordering::Less) => &unit + &one,
+            (unit, _) => unit,
+        }
+    };
+    let last = if end.len() > length {
+        largest
+    } else {
+        match unit_of(end) {
+            (unit, Ordering::Greater) => &unit - &one,
+            (unit, _) => unit,
+        }
+    };
+    //either bound can move past the other, or out of the unit length
+    if first > last || first.to_string().len() != unit_length {
+        None
+    } else {
+        Some((first, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_invalid, is_valid_id_with_policy, sum_invalid};
+
+    #[test]
+    fn arithmetic_matches_u128() {
+        let values: [u128; 6] = [
+            0,
+            1,
+            999_999_999,
+            1_000_000_000,
+            u64::MAX as u128,
+            u128::MAX,
+        ];
+        for a in values {
+            for b in values {
+                let (big_a, big_b) = (BigUint::from(a), BigUint::from(b));
+                assert_eq!(big_a.to_string(), a.to_string());
+                assert_eq!(big_a.cmp(&big_b), a.cmp(&b));
+                if let Some(sum) = a.checked_add(b) {
+                    assert_eq!(&big_a + &big_b, BigUint::from(sum));
+                }
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!(&big_a * &big_b, BigUint::from(product));
+                }
+                if a >= b {
+                    assert_eq!(&big_a - &big_b, BigUint::from(a - b));
+                }
+            }
+        }
+        let big = BigUint::from(u128::MAX);
+        assert_eq!((&big * &big).mul_small(2).div_small(2), &big * &big);
+        assert_eq!("000123".parse::<BigUint>(), Ok(BigUint::from(123u64)));
+        assert_eq!(BigUint::from(u128::MAX).to_u128(), Some(u128::MAX));
+        assert_eq!((&big + &big).to_u128(), None);
+    }
+
+    #[test]
+    fn big_totals_match_u64() {
+        let policies = [
+            RepetitionPolicy::default(),
+            RepetitionPolicy::Exactly(2),
+            RepetitionPolicy::Between(3, 5),
+            RepetitionPolicy::AtLeast(1),
+        ];
+        let ranges = [
+            (0, 0),
+            (1, 2_000),
+            (95, 115),
+            (998_990, 1_001_020),
+            (123_456, 987_654_321_987),
+            (0, u64::MAX),
+            (10, 9),
+        ];
+        for policy in policies {
+            for (start, end) in ranges {
+                let (big_start, big_end) = (BigUint::from(start), BigUint::from(end));
+                let count = count_invalid_big(&big_start, &big_end, policy);
+                assert_eq!(count, BigUint::from(count_invalid(start, end, policy)));
+                let sum = sum_invalid_big(&big_start, &big_end, policy);
+                assert_eq!(sum, BigUint::from(sum_invalid(start, end, policy)));
+            }
+        }
+        for id in 0..20_000u64 {
+            for policy in policies {
+                let big = BigUint::from(id);
+                let expected = is_valid_id_with_policy(id, policy);
+                assert_eq!(is_valid_big_id_with_policy(&big, policy), expected, "{id}");
+            }
+        }
+    }
+
+    #[test]
+    fn big_totals_go_past_u128() {
+        let start: BigUint = format!("1{}", "0".repeat(39)).parse().unwrap();
+        let end: BigUint = "9".repeat(40).parse().unwrap();
+        // 40-digit ids made of 20-digit or 8-digit units, minus the 4-digit ones that are both
+        let count = 9 * 10u128.pow(19) + 9 * 10u128.pow(7) - 9 * 10u128.pow(3);
+        let results = count_invalid_big(&start, &end, RepetitionPolicy::default());
+        assert_eq!(results, BigUint::from(count));
+        let results = sum_invalid_big(&start, &end, RepetitionPolicy::Exactly(2));
+        let expected = "495000000000000000000449999999999999999955000000000000000000";
+        assert_eq!(results.to_string(), expected);
+    }
+}
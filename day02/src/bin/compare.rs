@@ -0,0 +1,69 @@
+//! Times the arithmetic ID check against the regex reference over a range of IDs, and
+//! reports the first ID the two disagree on.
+//!
+//! Usage: compare [start] [end]
+//!
+//! Defaults to every ID below 10^7. Build with --release for meaningful numbers.
+
+use day02::RepetitionPolicy;
+use std::env;
+use std::process::ExitCode;
+use std::time::Instant;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (start, end) = match args.as_slice() {
+        [] => (0, 10_000_000),
+        [start, end] => match (start.parse(), end.parse()) {
+            (Ok(start), Ok(end)) => (start, end),
+            _ => return usage("start and end must be numbers"),
+        },
+        _ => return usage("expected a start and an end"),
+    };
+
+    let timer = Instant::now();
+    let native: Vec<u64> = (start..=end)
+        .filter(|&id| !day02::is_valid_id(id))
+        .collect();
+    let native_time = timer.elapsed();
+
+    let timer = Instant::now();
+    let policy = RepetitionPolicy::default();
+    let regex: Vec<u64> = (start..=end)
+        .filter(|&id| !day02::is_valid_id_by_regex(id, policy, 10))
+        .collect();
+    let regex_time = timer.elapsed();
+
+    println!("native: {} invalid in {native_time:?}", native.len());
+    println!("regex:  {} invalid in {regex_time:?}", regex.len());
+    //both lists are in order, so the first place they differ is the first ID they disagree on
+    let mismatch = native
+        .iter()
+        .zip(&regex)
+        .find(|(a, b)| a != b)
+        .map(|(&a, &b)| a.min(b))
+        .or_else(|| native.get(regex.len()).or(regex.get(native.len())).copied());
+    if let Some(id) = mismatch {
+        eprintln!(
+            "the two checks disagree on {id}: native says {}, regex says {}",
+            verdict(day02::is_valid_id(id)),
+            verdict(day02::is_valid_id_by_regex(id, policy, 10))
+        );
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "native is {:.1}x faster",
+        regex_time.as_secs_f64() / native_time.as_secs_f64()
+    );
+    ExitCode::SUCCESS
+}
+
+fn verdict(valid: bool) -> &'static str {
+    if valid { "valid" } else { "invalid" }
+}
+
+fn usage(problem: &str) -> ExitCode {
+    eprintln!("{problem}");
+    eprintln!("usage: compare [start] [end]");
+    ExitCode::FAILURE
+}
@@ -1,5 +1,71 @@
+mod big;
+mod rules;
+
+pub use big::{
+    BigUint, count_invalid_big, is_valid_big_id, is_valid_big_id_with_policy, sum_invalid_big,
+};
+pub use rules::{All, AlmostRepeated, Any, IdRule, Not, Palindrome, RunLongerThan};
+
 use fancy_regex::Regex;
-use std::{fs::File, io::BufRead, io::BufReader, sync::LazyLock};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::{error::Error, fmt, fs::File, io::Read};
+
+/// How many times a substring has to repeat to make up an ID for the ID to be invalid
+///
+/// The default, `AtLeast(2)`, is the rule is_valid_id uses.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RepetitionPolicy {
+    Exactly(u32),
+    AtLeast(u32),
+    /// inclusive on both ends
+    Between(u32, u32),
+}
+
+impl Default for RepetitionPolicy {
+    fn default() -> RepetitionPolicy {
+        RepetitionPolicy::AtLeast(2)
+    }
+}
+
+impl RepetitionPolicy {
+    /// Returns true if a substring repeated `repeats` times breaks the rule
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let policy = day02::RepetitionPolicy::Between(2, 3);
+    /// assert!(policy.allows(3));
+    /// assert!(!policy.allows(4));
+    /// ```
+    pub fn allows(&self, repeats: u32) -> bool {
+        match *self {
+            RepetitionPolicy::Exactly(n) => repeats == n,
+            RepetitionPolicy::AtLeast(n) => repeats >= n,
+            RepetitionPolicy::Between(n, m) => n <= repeats && repeats <= m,
+        }
+    }
+
+    // an id whose smallest unit repeats `repeats` times can also be read as a bigger unit
+    //   repeated any divisor of that many times
+    fn allows_divisor_of(&self, repeats: u32) -> bool {
+        divisors(repeats).any(|d| self.allows(d))
+    }
+
+    fn pattern(&self) -> Option<String> {
+        let (least, most) = match *self {
+            RepetitionPolicy::Exactly(n) => (n, Some(n)),
+            RepetitionPolicy::AtLeast(n) => (n, None),
+            RepetitionPolicy::Between(n, m) => (n, Some(m)),
+        };
+        let least = least.max(1);
+        match most {
+            Some(most) if most < least => None,
+            Some(most) => Some(format!(r"^([0-9a-z]+)\1{{{},{}}}$", least - 1, most - 1)),
+            None => Some(format!(r"^([0-9a-z]+)\1{{{},}}$", least - 1)),
+        }
+    }
+}
 
 /// Returns true if the id is valid.
 /// A valid ID is **not** made up of the same substring repeated two or more times
@@ -27,16 +93,194 @@ use std::{fs::File, io::BufRead, io::BufReader, sync::LazyLock};
 /// assert!(!so_many);
 /// ```
 pub fn is_valid_id(input: u64) -> bool {
-    let str_input = input.to_string();
-    let trimmed = str_input.trim();
-    static RE: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"^(\d+)(\1)+$").expect("weird regex pattern"));
-    // regex looks for a sequence of up to 6 digits that are repeated multiple times
-    // the number 6 is arbitrary and assumes that we are getting at most a 12-digit number,
-    //   so we only need to match up to half the digits.
-    let dupes = RE.is_match(trimmed).expect("weird regex match");
-    let valid = !dupes;
-    valid
+    is_valid_id_with_policy_in_radix(input, RepetitionPolicy::default(), 10)
+}
+
+/// The regex version of is_valid_id_with_policy_in_radix, kept as a reference to check
+/// the arithmetic one against. It writes the id out and backtracks over its digits, so
+/// it is much slower.
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+///
+/// ```
+/// use day02::RepetitionPolicy;
+/// for id in [99, 101, 123123, 1111111] {
+///     assert_eq!(
+///         day02::is_valid_id_by_regex(id, RepetitionPolicy::default(), 10),
+///         day02::is_valid_id(id)
+///     );
+/// }
+/// ```
+pub fn is_valid_id_by_regex(input: u64, policy: RepetitionPolicy, radix: u32) -> bool {
+    check_radix(radix);
+    if radix == 10 && policy == RepetitionPolicy::default() {
+        let str_input = input.to_string();
+        let trimmed = str_input.trim();
+        static RE: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^(\d+)(\1)+$").expect("weird regex pattern"));
+        let dupes = RE.is_match(trimmed).expect("weird regex match");
+        return !dupes;
+    }
+    let Some(pattern) = policy.pattern() else {
+        //nothing can repeat a number of times the policy forbids
+        return true;
+    };
+    //compiling the pattern is far slower than matching it, so keep one per policy around
+    static PATTERNS: LazyLock<Mutex<HashMap<RepetitionPolicy, Regex>>> =
+        LazyLock::new(|| Mutex::new(HashMap::new()));
+    let re = PATTERNS
+        .lock()
+        .expect("poisoned regex cache")
+        .entry(policy)
+        .or_insert_with(|| Regex::new(&pattern).expect("weird regex pattern"))
+        .clone();
+    let dupes = re
+        .is_match(&radix_digits(input, radix))
+        .expect("weird regex match");
+    !dupes
+}
+
+/// The shortest unit an ID is made of, and how many times it repeats
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Repetition {
+    pub unit: u64,
+    pub unit_length: u32,
+    pub repeats: u32,
+}
+
+impl fmt::Display for Repetition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} x{}", self.unit, self.repeats)
+    }
+}
+
+/// Finds the shortest unit that makes up the id when repeated.
+/// An id that isn't a repeat of anything is its own unit, repeated once.
+///
+/// # Examples
+///
+/// ```
+/// let result = day02::classify_id(824824824);
+/// assert_eq!(result.unit, 824);
+/// assert_eq!(result.repeats, 3);
+/// ```
+/// ```
+/// let result = day02::classify_id(11111111);
+/// assert_eq!((result.unit, result.repeats), (1, 8));
+/// ```
+/// ```
+/// let result = day02::classify_id(123321);
+/// assert_eq!((result.unit, result.repeats), (123321, 1));
+/// ```
+pub fn classify_id(input: u64) -> Repetition {
+    shortest_repetition(input, 10)
+}
+
+// An id of `length` digits is a unit repeated `repeats` times exactly when it divides by
+// the repeater (1001 for two 3-digit decimal units) and the quotient doesn't start with
+// a zero, so trying the unit lengths from the shortest finds the smallest unit without
+// writing out any digits.
+fn shortest_repetition(input: u64, radix: u32) -> Repetition {
+    let length = digit_count(input, radix);
+    for unit_length in divisors(length) {
+        let repeats = length / unit_length;
+        let repeater = (power(radix, length) - 1) / (power(radix, unit_length) - 1);
+        let unit = u128::from(input) / repeater;
+        if repeats == 1
+            || (u128::from(input) % repeater == 0 && unit >= power(radix, unit_length - 1))
+        {
+            return Repetition {
+                unit: unit as u64,
+                unit_length,
+                repeats,
+            };
+        }
+    }
+    unreachable!("every id repeats itself once")
+}
+
+/// Returns true if the id is **not** made up of the same substring repeated a number of
+/// times the policy forbids
+///
+/// # Examples
+///
+/// ```
+/// use day02::RepetitionPolicy;
+/// assert!(!day02::is_valid_id_with_policy(123123, RepetitionPolicy::Exactly(2)));
+/// assert!(day02::is_valid_id_with_policy(123123123, RepetitionPolicy::Exactly(2)));
+/// assert!(!day02::is_valid_id_with_policy(1111, RepetitionPolicy::Exactly(2)));
+/// ```
+/// ```
+/// use day02::RepetitionPolicy;
+/// assert!(day02::is_valid_id_with_policy(1111, RepetitionPolicy::Between(3, 3)));
+/// assert!(!day02::is_valid_id_with_policy(1111, RepetitionPolicy::Between(3, 4)));
+/// ```
+pub fn is_valid_id_with_policy(input: u64, policy: RepetitionPolicy) -> bool {
+    is_valid_id_with_policy_in_radix(input, policy, 10)
+}
+
+/// Like is_valid_id, but looks for repeats among the id's digits in another radix
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+///
+/// ```
+/// assert!(!day02::is_valid_id_in_radix(0xabab, 16));
+/// assert!(day02::is_valid_id_in_radix(0xabab, 10));
+/// assert!(!day02::is_valid_id_in_radix(0b101101, 2));
+/// ```
+pub fn is_valid_id_in_radix(input: u64, radix: u32) -> bool {
+    is_valid_id_with_policy_in_radix(input, RepetitionPolicy::default(), radix)
+}
+
+/// Like is_valid_id_with_policy, but looks for repeats among the id's digits in another
+/// radix
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+///
+/// ```
+/// use day02::RepetitionPolicy;
+/// let id = u64::from_str_radix("zzz", 36).unwrap();
+/// assert!(!day02::is_valid_id_with_policy_in_radix(id, RepetitionPolicy::Exactly(3), 36));
+/// assert!(day02::is_valid_id_with_policy_in_radix(id, RepetitionPolicy::Exactly(2), 36));
+/// ```
+pub fn is_valid_id_with_policy_in_radix(input: u64, policy: RepetitionPolicy, radix: u32) -> bool {
+    check_radix(radix);
+    let repetition = shortest_repetition(input, radix);
+    !policy.allows_divisor_of(repetition.repeats)
+}
+
+// the id written out in the radix, with lowercase letters past 9
+fn radix_digits(input: u64, radix: u32) -> String {
+    let mut digits: Vec<char> = Vec::new();
+    let mut remaining = input;
+    loop {
+        let digit = (remaining % u64::from(radix)) as u32;
+        digits.push(char::from_digit(digit, radix).expect("digit is below the radix"));
+        remaining /= u64::from(radix);
+        if remaining == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+fn check_radix(radix: u32) {
+    assert!(
+        (2..=36).contains(&radix),
+        "radix must be between 2 and 36, got {radix}"
+    );
 }
 
 /// Returns a vector of invalid IDs from an inclusive range between start and end inputs
@@ -56,16 +300,494 @@ pub fn is_valid_id(input: u64) -> bool {
 /// assert!(results.is_empty())
 /// ```
 pub fn get_invalid_ids_from_range(start: u64, end: u64) -> Vec<u64> {
-    let mut result: Vec<u64> = Vec::new();
-    for i in start..=end {
-        if !is_valid_id(i) {
-            result.push(i);
+    get_invalid_ids_from_range_with_policy(start, end, RepetitionPolicy::default())
+}
+
+/// Returns a vector of invalid IDs from an inclusive range between start and end inputs
+/// ID validity determined by day02::is_valid_id_with_policy()
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// let results = day02::get_invalid_ids_from_range_with_policy(95, 1200, RepetitionPolicy::Exactly(2));
+/// assert_eq!(results, [99, 1010, 1111])
+/// ```
+pub fn get_invalid_ids_from_range_with_policy(
+    start: u64,
+    end: u64,
+    policy: RepetitionPolicy,
+) -> Vec<u64> {
+    InvalidIds::new(start, end, policy).collect()
+}
+
+/// Returns a vector of invalid IDs from an inclusive range between start and end inputs
+/// ID validity determined by day02::is_valid_id_with_policy_in_radix()
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// let results = day02::get_invalid_ids_from_range_in_radix(0x10, 0xffff, RepetitionPolicy::Exactly(2), 16);
+/// assert_eq!(results.len(), 15 + 15 * 16);
+/// assert_eq!(results[..3], [0x11, 0x22, 0x33]);
+/// ```
+pub fn get_invalid_ids_from_range_in_radix(
+    start: u64,
+    end: u64,
+    policy: RepetitionPolicy,
+    radix: u32,
+) -> Vec<u64> {
+    InvalidIds::with_radix(start, end, policy, radix).collect()
+}
+
+/// Lazily walks the invalid IDs of an inclusive range in increasing order, building each
+/// one from its repeating unit. Memory stays the same however wide the range is.
+///
+/// # Examples
+/// ```
+/// use day02::{InvalidIds, RepetitionPolicy};
+/// let mut ids = InvalidIds::new(95, 10_000_000_000_000_000_000, RepetitionPolicy::default());
+/// assert_eq!(ids.next(), Some(99));
+/// assert_eq!(ids.next(), Some(111));
+/// assert_eq!(ids.nth(1_000), Some(919_919));
+/// ```
+pub struct InvalidIds {
+    end: u64,
+    policy: RepetitionPolicy,
+    radix: u32,
+    length: u32,
+    // for every allowed repeat count at the current length: the number that turns a unit
+    //   into its id, the next unit to hand out and the last unit in range
+    streams: Vec<(u128, u128, u128)>,
+}
+
+impl InvalidIds {
+    pub fn new(start: u64, end: u64, policy: RepetitionPolicy) -> InvalidIds {
+        InvalidIds::with_radix(start, end, policy, 10)
+    }
+
+    /// Walks the IDs that repeat among their digits in the radix
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radix is not between 2 and 36
+    pub fn with_radix(start: u64, end: u64, policy: RepetitionPolicy, radix: u32) -> InvalidIds {
+        check_radix(radix);
+        let mut ids = InvalidIds {
+            end,
+            policy,
+            radix,
+            length: digit_count(start, radix),
+            streams: Vec::new(),
+        };
+        if start <= end {
+            ids.fill_streams(start);
+        } else {
+            ids.length = u32::MAX;
+        }
+        ids
+    }
+
+    fn fill_streams(&mut self, start: u64) {
+        self.streams.clear();
+        for repeats in divisors(self.length) {
+            if !self.policy.allows(repeats) {
+                continue;
+            }
+            let span = unit_span(self.length, repeats, start, self.end, self.radix);
+            if let Some((repeater, first, last)) = span {
+                self.streams.push((repeater, first, last));
+            }
+        }
+    }
+}
+
+impl Iterator for InvalidIds {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            //the smallest id any stream has left, several streams can share it
+            let smallest = self
+                .streams
+                .iter()
+                .filter(|(_, next, last)| next <= last)
+                .map(|(repeater, next, _)| next * repeater)
+                .min();
+            if let Some(id) = smallest {
+                for (repeater, next, _) in self.streams.iter_mut() {
+                    if *next * *repeater == id {
+                        *next += 1;
+                    }
+                }
+                return Some(id as u64);
+            }
+            if self.length >= digit_count(self.end, self.radix) {
+                self.streams.clear();
+                return None;
+            }
+            self.length += 1;
+            self.fill_streams(power(self.radix, self.length - 1) as u64);
         }
     }
-    //println!("found {result:?} invalid IDs");
+}
+
+/// Counts the invalid IDs in an inclusive range under a policy, without visiting each ID
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// let results = day02::count_invalid(1, 10_000, RepetitionPolicy::Exactly(2));
+/// assert_eq!(results, 99)
+/// ```
+pub fn count_invalid(start: u64, end: u64, policy: RepetitionPolicy) -> u128 {
+    count_invalid_in_radix(start, end, policy, 10)
+}
+
+/// Sums the invalid IDs in an inclusive range under a policy, without visiting each ID
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// let results = day02::sum_invalid(95, 1200, RepetitionPolicy::Exactly(2));
+/// assert_eq!(results, 99 + 1010 + 1111)
+/// ```
+pub fn sum_invalid(start: u64, end: u64, policy: RepetitionPolicy) -> u128 {
+    sum_invalid_in_radix(start, end, policy, 10)
+}
+
+/// Counts the invalid IDs in an inclusive range under a policy, reading each ID's digits
+/// in the radix. A policy that allows a single repeat makes every ID invalid, so the count
+/// can be one more than u64::MAX.
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// // 0b11, 0b1010, 0b1111
+/// let results = day02::count_invalid_in_radix(0, 0b1111, RepetitionPolicy::Exactly(2), 2);
+/// assert_eq!(results, 3)
+/// ```
+pub fn count_invalid_in_radix(start: u64, end: u64, policy: RepetitionPolicy, radix: u32) -> u128 {
+    check_radix(radix);
+    let (count, _) = invalid_id_totals(start, end, policy, radix);
+    count as u128
+}
+
+/// Sums the invalid IDs in an inclusive range under a policy, reading each ID's digits in
+/// the radix
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+/// ```
+/// use day02::RepetitionPolicy;
+/// let results = day02::sum_invalid_in_radix(0, 0b1111, RepetitionPolicy::Exactly(2), 2);
+/// assert_eq!(results, 0b11 + 0b1010 + 0b1111)
+/// ```
+pub fn sum_invalid_in_radix(start: u64, end: u64, policy: RepetitionPolicy, radix: u32) -> u128 {
+    check_radix(radix);
+    let (_, sum) = invalid_id_totals(start, end, policy, radix);
+    sum as u128
+}
+
+/// Counts the invalid IDs in an inclusive range without visiting each ID
+///
+/// # Examples
+/// ```
+/// let results = day02::count_invalid_ids_in_range(95, 115);
+/// assert_eq!(results, 2)
+/// ```
+/// ```
+/// let results = day02::count_invalid_ids_in_range(1, 10_000_000_000_000_000_000);
+/// assert_eq!(results, 1_001_000_106)
+/// ```
+pub fn count_invalid_ids_in_range(start: u64, end: u64) -> u64 {
+    //under the default policy only a sliver of the ids are invalid, so this fits a u64
+    count_invalid(start, end, RepetitionPolicy::default()) as u64
+}
+
+/// Sums the invalid IDs in an inclusive range without visiting each ID
+///
+/// # Examples
+/// ```
+/// let results = day02::sum_invalid_ids_in_range(95, 115);
+/// assert_eq!(results, 210)
+/// ```
+/// ```
+/// let results = day02::sum_invalid_ids_in_range(1188511880, 1188511890);
+/// assert_eq!(results, 1188511885)
+/// ```
+pub fn sum_invalid_ids_in_range(start: u64, end: u64) -> u128 {
+    sum_invalid(start, end, RepetitionPolicy::default())
+}
+
+/// Counts the invalid IDs up to and including n, so the invalid IDs of a range are
+/// `invalid_rank(end) - invalid_rank(start - 1)`
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::invalid_rank(98), 8);
+/// assert_eq!(day02::invalid_rank(99), 9);
+/// assert_eq!(day02::invalid_rank(115) - day02::invalid_rank(94), 2);
+/// ```
+pub fn invalid_rank(n: u64) -> u64 {
+    count_invalid_ids_in_range(0, n)
+}
+
+/// Returns the k-th invalid ID, counting from 1, so that `invalid_select(invalid_rank(id))`
+/// is `id` for every invalid ID. Returns None for 0 and past the last invalid u64.
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::invalid_select(1), Some(11));
+/// assert_eq!(day02::invalid_select(10), Some(111));
+/// assert_eq!(day02::invalid_select(1_001_000_106), Some(9_999_999_999_999_999_999));
+/// assert_eq!(day02::invalid_select(0), None);
+/// ```
+pub fn invalid_select(k: u64) -> Option<u64> {
+    if k == 0 || k > invalid_rank(u64::MAX) {
+        return None;
+    }
+    //the rank only grows, so look for the first id whose rank reaches k
+    let mut low: u64 = 0;
+    let mut high: u64 = u64::MAX;
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if invalid_rank(middle) >= k {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    Some(low)
+}
+
+/// Returns the smallest invalid ID after n, if there is one up to and including u64::MAX
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::next_invalid_id(100), Some(111));
+/// assert_eq!(day02::next_invalid_id(999_999_999_999), Some(1_111_111_111_111));
+/// ```
+pub fn next_invalid_id(n: u64) -> Option<u64> {
+    let start = n.checked_add(1)?;
+    InvalidIds::new(start, u64::MAX, RepetitionPolicy::default()).next()
+}
+
+/// Returns the largest invalid ID before n
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::prev_invalid_id(1_000), Some(999));
+/// assert_eq!(day02::prev_invalid_id(1_000_000_000_000), Some(999_999_999_999));
+/// assert_eq!(day02::prev_invalid_id(11), None);
+/// ```
+pub fn prev_invalid_id(n: u64) -> Option<u64> {
+    let end = n.checked_sub(1)?;
+    //the longest ids come last, so the first length with any invalid id up to `end` wins
+    for length in (2..=digit_count(end, 10)).rev() {
+        let largest = divisors(length)
+            .filter(|&repeats| repeats > 1)
+            .filter_map(|repeats| unit_span(length, repeats, 0, end, 10))
+            .map(|(repeater, _, last)| last * repeater)
+            .max();
+        if let Some(id) = largest {
+            return Some(id as u64);
+        }
+    }
+    None
+}
+
+// Every repeater for ids of `length` digits divides 10^length - 1 without dividing
+// 10^k - 1 for any shorter k, so they all share that number's largest cyclotomic factor
+// and two invalid ids of one length are never next to each other. 10^length itself is
+// always valid, so an invalid id is always one step away from a valid one.
+
+/// Returns the smallest valid ID after n, if there is one up to and including u64::MAX
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::next_valid_id(98), Some(100));
+/// assert_eq!(day02::next_valid_id(999_999_999_998), Some(1_000_000_000_000));
+/// assert_eq!(day02::next_valid_id(u64::MAX - 1), Some(u64::MAX));
+/// assert_eq!(day02::next_valid_id(u64::MAX), None);
+/// ```
+pub fn next_valid_id(n: u64) -> Option<u64> {
+    let candidate = n.checked_add(1)?;
+    if is_valid_id(candidate) {
+        Some(candidate)
+    } else {
+        candidate.checked_add(1)
+    }
+}
+
+/// Returns the largest valid ID before n
+///
+/// # Examples
+/// ```
+/// assert_eq!(day02::prev_valid_id(112), Some(110));
+/// assert_eq!(day02::prev_valid_id(1_000_000_000_000), Some(999_999_999_998));
+/// assert_eq!(day02::prev_valid_id(0), None);
+/// ```
+pub fn prev_valid_id(n: u64) -> Option<u64> {
+    let candidate = n.checked_sub(1)?;
+    if is_valid_id(candidate) {
+        Some(candidate)
+    } else {
+        candidate.checked_sub(1)
+    }
+}
+
+// Every id of `length` digits is its smallest unit repeated q times for some q dividing
+// the length, and it breaks the policy when some divisor of q is a forbidden count.
+// The ids we can build from a unit repeated `e` times are exactly the ones where e
+// divides q, so Möbius inversion over the divisors of e tells us how much each of those
+// sets has to be added or removed to count every invalid id exactly once.
+fn invalid_id_totals(start: u64, end: u64, policy: RepetitionPolicy, radix: u32) -> (i128, i128) {
+    let mut count: i128 = 0;
+    let mut sum: i128 = 0;
+    if start > end {
+        return (count, sum);
+    }
+    for length in digit_count(start, radix)..=digit_count(end, radix) {
+        for repeats in divisors(length) {
+            let weight: i128 = divisors(repeats)
+                .filter(|&q| policy.allows_divisor_of(q))
+                .map(|q| i128::from(mobius(repeats / q)))
+                .sum();
+            if weight == 0 {
+                continue;
+            }
+            if let Some((repeater, first, last)) = unit_span(length, repeats, start, end, radix) {
+                let units = last - first + 1;
+                //halve whichever factor is even, so a whole binary range can't overflow
+                let unit_sum = if units.is_multiple_of(2) {
+                    (first + last) * (units / 2)
+                } else {
+                    (first + last) / 2 * units
+                };
+                let (units, unit_sum) = (units as i128, unit_sum as i128);
+                count += weight * units;
+                sum += weight * unit_sum * repeater as i128;
+            }
+        }
+    }
+    (count, sum)
+}
+
+// For ids of `length` digits made of a unit repeated `repeats` times, returns the number
+// that turns a unit into its id (1001 for two 3-digit decimal units) and the units whose
+// ids fall inside the range
+fn unit_span(
+    length: u32,
+    repeats: u32,
+    start: u64,
+    end: u64,
+    radix: u32,
+) -> Option<(u128, u128, u128)> {
+    let unit_length = length / repeats;
+    let repeater = (power(radix, length) - 1) / (power(radix, unit_length) - 1);
+    //0 is the one id whose leading digit is a zero
+    let smallest_unit = if length == 1 {
+        0
+    } else {
+        power(radix, unit_length - 1)
+    };
+    let largest_unit = power(radix, unit_length) - 1;
+    let first = u128::from(start).div_ceil(repeater).max(smallest_unit);
+    let last = (u128::from(end) / repeater).min(largest_unit);
+    if first > last {
+        None
+    } else {
+        Some((repeater, first, last))
+    }
+}
+
+fn power(radix: u32, exponent: u32) -> u128 {
+    u128::from(radix).pow(exponent)
+}
+
+fn digit_count(n: u64, radix: u32) -> u32 {
+    n.checked_ilog(u64::from(radix)).unwrap_or(0) + 1
+}
+
+fn divisors(n: u32) -> impl Iterator<Item = u32> {
+    (1..=n).filter(move |&d| n.is_multiple_of(d))
+}
+
+fn mobius(n: u32) -> i32 {
+    let mut remaining = n;
+    let mut result = 1;
+    let mut factor = 2;
+    while factor * factor <= remaining {
+        if remaining.is_multiple_of(factor) {
+            remaining /= factor;
+            if remaining.is_multiple_of(factor) {
+                return 0;
+            }
+            result = -result;
+        }
+        factor += 1;
+    }
+    if remaining > 1 {
+        result = -result;
+    }
     result
 }
 
+/// What to do with a range written backwards, like "50-10"
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ReversedRanges {
+    #[default]
+    Reject,
+    /// treat it as the same range written forwards
+    Swap,
+}
+
+/// What went wrong while parsing a range list
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RangeErrorKind {
+    UnexpectedCharacter,
+    MissingNumber,
+    MissingDash,
+    NumberTooLarge,
+    Reversed,
+    Unreadable,
+}
+
+/// A bad token in a range list, located by its byte offset from the start of the input
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RangeParseError {
+    pub offset: usize,
+    pub token: String,
+    pub kind: RangeErrorKind,
+}
+
+impl fmt::Display for RangeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            RangeErrorKind::UnexpectedCharacter => "unexpected character",
+            RangeErrorKind::MissingNumber => "expected a number but found",
+            RangeErrorKind::MissingDash => "expected '-' but found",
+            RangeErrorKind::NumberTooLarge => "number is too large",
+            RangeErrorKind::Reversed => "range ends before it starts",
+            RangeErrorKind::Unreadable => "unable to read input",
+        };
+        write!(f, "byte {}: {reason} {:?}", self.offset, self.token)
+    }
+}
+
+impl Error for RangeParseError {}
+
 /// Parses a string of the form "start-end" and returns a tuple of the start and end
 ///
 /// # Examples
@@ -79,37 +801,923 @@ pub fn get_invalid_ids_from_range(start: u64, end: u64) -> Vec<u64> {
 /// assert_eq!(results, (2121212118, 2121212124))
 /// ```
 pub fn parse_range_from_string(input: String) -> (u64, u64) {
-    //println!("parsing range from {input}");
-    let parts: Vec<&str> = input.split('-').collect();
-    let front = parts[0].trim();
-    let back = parts[1].trim();
-    let start: u64 = front.parse().expect("unable to parse front of range");
-    let end: u64 = back.parse().expect("unable to parse back of range");
-    (start, end)
+    parse_range(&input, ReversedRanges::Reject)
+        .unwrap_or_else(|e| panic!("unable to parse range: {e}"))
+}
+
+/// Parses exactly one "start-end" range, allowing whitespace around the parts
+///
+/// # Examples
+///
+/// ```
+/// use day02::ReversedRanges;
+/// assert_eq!(day02::parse_range(" 50 -\n10 ", ReversedRanges::Swap), Ok((10, 50)));
+/// let error = day02::parse_range("50-10", ReversedRanges::Reject).unwrap_err();
+/// assert_eq!(error.kind, day02::RangeErrorKind::Reversed);
+/// ```
+pub fn parse_range(input: &str, reversed: ReversedRanges) -> Result<(u64, u64), RangeParseError> {
+    parse_range_in_radix(input, reversed, 10)
+}
+
+/// Like parse_range, with the numbers written in the radix. Letters can be either case.
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+///
+/// ```
+/// use day02::ReversedRanges;
+/// assert_eq!(day02::parse_range_in_radix("ff-1A0", ReversedRanges::Reject, 16), Ok((255, 416)));
+/// ```
+pub fn parse_range_in_radix(
+    input: &str,
+    reversed: ReversedRanges,
+    radix: u32,
+) -> Result<(u64, u64), RangeParseError> {
+    check_radix(radix);
+    let mut scanner = RangeScanner {
+        text: input,
+        pos: 0,
+        radix,
+    };
+    scanner.skip_space();
+    let range = scanner.range(reversed, parse_u64)?;
+    scanner.skip_space();
+    match scanner.peek() {
+        None => Ok(range),
+        Some(_) => Err(scanner.error(RangeErrorKind::UnexpectedCharacter)),
+    }
+}
+
+/// Parses a comma separated list of ranges. Whitespace and newlines may appear between
+/// any of the tokens, a line break can stand in for the comma between two ranges, and
+/// the list may end with a comma.
+///
+/// # Examples
+///
+/// ```
+/// use day02::ReversedRanges;
+/// let input = "11-22,\n 95 - 115 ,\n998-1012,\n";
+/// let results = day02::parse_range_list(input, ReversedRanges::Reject);
+/// assert_eq!(results, Ok(vec![(11, 22), (95, 115), (998, 1012)]));
+/// ```
+/// ```
+/// use day02::ReversedRanges;
+/// let error = day02::parse_range_list("11-22,9522,", ReversedRanges::Reject).unwrap_err();
+/// assert_eq!(error.kind, day02::RangeErrorKind::MissingDash);
+/// assert_eq!(error.offset, 10);
+/// ```
+pub fn parse_range_list(
+    input: &str,
+    reversed: ReversedRanges,
+) -> Result<Vec<(u64, u64)>, RangeParseError> {
+    parse_range_list_in_radix(input, reversed, 10)
+}
+
+/// Like parse_range_list, with the numbers written in the radix. Letters can be either
+/// case.
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+///
+/// # Examples
+///
+/// ```
+/// use day02::ReversedRanges;
+/// let results = day02::parse_range_list_in_radix("a-Z,\n10-zz", ReversedRanges::Reject, 36);
+/// assert_eq!(results, Ok(vec![(10, 35), (36, 1295)]));
+/// ```
+/// ```
+/// use day02::ReversedRanges;
+/// let error = day02::parse_range_list_in_radix("1-2,3-4", ReversedRanges::Reject, 2).unwrap_err();
+/// assert_eq!(error.kind, day02::RangeErrorKind::MissingNumber);
+/// assert_eq!(error.offset, 2);
+/// ```
+pub fn parse_range_list_in_radix(
+    input: &str,
+    reversed: ReversedRanges,
+    radix: u32,
+) -> Result<Vec<(u64, u64)>, RangeParseError> {
+    check_radix(radix);
+    let mut scanner = RangeScanner {
+        text: input,
+        pos: 0,
+        radix,
+    };
+    scanner.list(reversed, parse_u64)
+}
+
+/// Like parse_range_list, for decimal bounds of any length
+///
+/// # Examples
+///
+/// ```
+/// use day02::{BigUint, ReversedRanges};
+/// let input = "11-22,\n1000000000000000000000-1000000000000000000000000\n";
+/// let results = day02::parse_big_range_list(input, ReversedRanges::Reject).unwrap();
+/// assert_eq!(results[0], (BigUint::from(11u64), BigUint::from(22u64)));
+/// assert_eq!(results[1].1, BigUint::from(10u128.pow(24)));
+/// ```
+pub fn parse_big_range_list(
+    input: &str,
+    reversed: ReversedRanges,
+) -> Result<Vec<(BigUint, BigUint)>, RangeParseError> {
+    let mut scanner = RangeScanner {
+        text: input,
+        pos: 0,
+        radix: 10,
+    };
+    scanner.list(reversed, |token, _| token.parse().ok())
+}
+
+/// Reads a whole file as a range list, see parse_range_list
+pub fn read_range_list(
+    input: File,
+    reversed: ReversedRanges,
+) -> Result<Vec<(u64, u64)>, RangeParseError> {
+    read_range_list_in_radix(input, reversed, 10)
+}
+
+/// Reads a whole file as a range list in the radix, see parse_range_list_in_radix
+pub fn read_range_list_in_radix(
+    mut input: File,
+    reversed: ReversedRanges,
+    radix: u32,
+) -> Result<Vec<(u64, u64)>, RangeParseError> {
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| RangeParseError {
+            offset: 0,
+            token: e.to_string(),
+            kind: RangeErrorKind::Unreadable,
+        })?;
+    parse_range_list_in_radix(&text, reversed, radix)
+}
+
+struct RangeScanner<'a> {
+    text: &'a str,
+    pos: usize,
+    radix: u32,
+}
+
+// turns a token of digits in the radix into a number, or None if it doesn't fit
+type ParseNumber<T> = fn(&str, u32) -> Option<T>;
+
+fn parse_u64(token: &str, radix: u32) -> Option<u64> {
+    u64::from_str_radix(token, radix).ok()
+}
+
+impl RangeScanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    // an error pointing at whatever character comes next
+    fn error(&self, kind: RangeErrorKind) -> RangeParseError {
+        RangeParseError {
+            offset: self.pos,
+            token: self.peek().map(String::from).unwrap_or_default(),
+            kind,
+        }
+    }
+
+    fn list<T: Ord>(
+        &mut self,
+        reversed: ReversedRanges,
+        parse: ParseNumber<T>,
+    ) -> Result<Vec<(T, T)>, RangeParseError> {
+        let mut result: Vec<(T, T)> = Vec::new();
+        self.skip_space();
+        while self.peek().is_some() {
+            result.push(self.range(reversed, parse)?);
+            let range_end = self.pos;
+            self.skip_space();
+            let new_line = self.text[range_end..self.pos].contains('\n');
+            match self.peek() {
+                None => break,
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_space();
+                }
+                //a list split over several lines doesn't need a comma at the end of each line
+                Some(c) if new_line && c.is_digit(self.radix) => {}
+                Some(_) => return Err(self.error(RangeErrorKind::UnexpectedCharacter)),
+            }
+        }
+        Ok(result)
+    }
+
+    fn range<T: Ord>(
+        &mut self,
+        reversed: ReversedRanges,
+        parse: ParseNumber<T>,
+    ) -> Result<(T, T), RangeParseError> {
+        let range_start = self.pos;
+        let start = self.number(parse)?;
+        self.skip_space();
+        if self.peek() != Some('-') {
+            return Err(self.error(RangeErrorKind::MissingDash));
+        }
+        self.pos += 1;
+        self.skip_space();
+        let end = self.number(parse)?;
+        if start <= end {
+            return Ok((start, end));
+        }
+        match reversed {
+            ReversedRanges::Swap => Ok((end, start)),
+            ReversedRanges::Reject => Err(RangeParseError {
+                offset: range_start,
+                token: self.text[range_start..self.pos].to_string(),
+                kind: RangeErrorKind::Reversed,
+            }),
+        }
+    }
+
+    fn number<T>(&mut self, parse: ParseNumber<T>) -> Result<T, RangeParseError> {
+        let digits_start = self.pos;
+        let digits = self.text[self.pos..]
+            .bytes()
+            .take_while(|&b| char::from(b).is_digit(self.radix))
+            .count();
+        if digits == 0 {
+            return Err(self.error(RangeErrorKind::MissingNumber));
+        }
+        self.pos += digits;
+        let token = &self.text[digits_start..self.pos];
+        parse(token, self.radix).ok_or_else(|| RangeParseError {
+            offset: digits_start,
+            token: token.to_string(),
+            kind: RangeErrorKind::NumberTooLarge,
+        })
+    }
+}
+
+pub fn sum_invalid_ids(input: File) -> u128 {
+    sum_invalid_ids_with_policy(input, RepetitionPolicy::default())
+}
+
+/// Sums the invalid IDs, as decided by the policy, of every range in the file
+/// Ranges that overlap are merged first, so every ID is counted once.
+pub fn sum_invalid_ids_with_policy(input: File, policy: RepetitionPolicy) -> u128 {
+    sum_invalid_ids_in_radix(input, policy, 10)
 }
 
-pub fn sum_invalid_ids(input: File) -> u64 {
-    let mut result: u64 = 0;
-    let buf = BufReader::new(input);
-    for line in buf.lines() {
-        let validated_line = line.expect("weird line");
-        let ranges: Vec<&str> = validated_line.split(",").collect();
-        for range in ranges {
-            let (start, end) = parse_range_from_string(range.to_string());
-            let invalid_ids = get_invalid_ids_from_range(start, end);
-            for id in invalid_ids {
-                result += id;
+/// Sums the invalid IDs, as decided by the policy, of every range in a file written in
+/// the radix. The IDs repeat among their digits in that radix too.
+///
+/// # Panics
+///
+/// Panics if the radix is not between 2 and 36
+pub fn sum_invalid_ids_in_radix(input: File, policy: RepetitionPolicy, radix: u32) -> u128 {
+    //the merged ranges don't overlap, so even every u64 summed fits in a u128
+    let mut result: u128 = 0;
+    let mut ranges = read_ranges(input, radix);
+    for (start, end) in merge_overlapping_ranges(&mut ranges) {
+        result += sum_invalid_in_radix(start, end, policy, radix);
+    }
+    result
+}
+
+/// Sums the IDs the rule rejects in every range in the file
+/// Ranges that overlap are merged first, so every ID is counted once.
+///
+/// Rules other than a RepetitionPolicy check every ID of every range.
+pub fn sum_invalid_ids_with_rule<R: IdRule + ?Sized>(input: File, rule: &R) -> u128 {
+    let mut result: u128 = 0;
+    let mut ranges = read_ranges(input, 10);
+    for (start, end) in merge_overlapping_ranges(&mut ranges) {
+        result += rule.sum_range(start, end);
+    }
+    result
+}
+
+/// Sums the invalid IDs, as decided by the policy, of every range in a file whose
+/// bounds can have any number of digits
+/// Ranges that overlap are merged first, so every ID is counted once.
+pub fn sum_invalid_big_ids(mut input: File, policy: RepetitionPolicy) -> BigUint {
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .unwrap_or_else(|e| panic!("unable to read ranges: {e}"));
+    let mut ranges = parse_big_range_list(&text, ReversedRanges::Reject)
+        .unwrap_or_else(|e| panic!("unable to parse ranges: {e}"));
+    let mut result = BigUint::default();
+    for (start, end) in merge_overlapping_ranges(&mut ranges) {
+        result = result + sum_invalid_big(&start, &end, policy);
+    }
+    result
+}
+
+/// The invalid IDs found in one range of the input, with why each is invalid
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RangeReport {
+    pub start: u64,
+    pub end: u64,
+    pub invalid_ids: Vec<(u64, Repetition)>,
+    pub sum: u128,
+}
+
+/// Every invalid ID of the input grouped by the range it came from.
+/// Displays as one line per range followed by one indented line per ID.
+///
+/// An ID inside overlapping ranges shows up under each of them, but only counts once
+/// towards the total.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InvalidIdReport {
+    pub ranges: Vec<RangeReport>,
+    pub overlaps: Vec<((u64, u64), (u64, u64))>,
+    pub total: u128,
+}
+
+impl fmt::Display for InvalidIdReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for range in &self.ranges {
+            writeln!(
+                f,
+                "{}-{}: {} invalid, sum {}",
+                range.start,
+                range.end,
+                range.invalid_ids.len(),
+                range.sum
+            )?;
+            for (id, repetition) in &range.invalid_ids {
+                writeln!(f, "  {id} = {repetition}")?;
             }
         }
+        for ((a, b), (c, d)) in &self.overlaps {
+            writeln!(f, "overlap {a}-{b} and {c}-{d}")?;
+        }
+        writeln!(f, "total {}", self.total)
+    }
+}
+
+/// Like sum_invalid_ids_with_policy, but lists every invalid ID and its repeating unit
+///
+/// Every invalid ID is kept in memory, so this is meant for inputs small enough to read.
+pub fn sum_invalid_ids_report(input: File, policy: RepetitionPolicy) -> InvalidIdReport {
+    let mut input_ranges = read_ranges(input, 10);
+    let mut ranges: Vec<RangeReport> = Vec::new();
+    for &(start, end) in &input_ranges {
+        let ids = get_invalid_ids_from_range_with_policy(start, end, policy);
+        let sum: u128 = ids.iter().map(|&id| u128::from(id)).sum();
+        ranges.push(RangeReport {
+            start,
+            end,
+            invalid_ids: ids.into_iter().map(|id| (id, classify_id(id))).collect(),
+            sum,
+        });
+    }
+    let overlaps = overlapping_ranges(&input_ranges);
+    let mut total: u128 = 0;
+    for (start, end) in merge_overlapping_ranges(&mut input_ranges) {
+        total += sum_invalid(start, end, policy);
+    }
+    InvalidIdReport {
+        ranges,
+        overlaps,
+        total,
+    }
+}
+
+/// Merges overlapping ranges and returns the shortened, sorted range vector
+///
+/// # Examples
+/// ```
+/// let mut input: Vec<(u64, u64)> = vec![(40, 100), (10, 50), (200, 300), (95, 115)];
+/// let result = day02::merge_overlapping_ranges(&mut input);
+/// assert_eq!(result, [(10, 115), (200, 300)]);
+/// ```
+pub fn merge_overlapping_ranges<T: Ord + Clone>(arr: &mut [(T, T)]) -> Vec<(T, T)> {
+    let mut result: Vec<(T, T)> = Vec::new();
+    arr.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (a, b) in arr.iter() {
+        match result.last_mut() {
+            Some((_, y)) if a <= y => {
+                //a is in the current range, stretch it if b is past the end
+                if b > y {
+                    *y = b.clone();
+                }
+            }
+            _ => result.push((a.clone(), b.clone())),
+        }
     }
     result
 }
 
+/// Lists every pair of ranges that share at least one ID, earlier input range first
+///
+/// # Examples
+/// ```
+/// let input: Vec<(u64, u64)> = vec![(40, 100), (10, 50), (200, 300), (95, 115)];
+/// let result = day02::overlapping_ranges(&input);
+/// assert_eq!(result, [((40, 100), (10, 50)), ((40, 100), (95, 115))]);
+/// ```
+pub fn overlapping_ranges(ranges: &[(u64, u64)]) -> Vec<((u64, u64), (u64, u64))> {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|&i| ranges[i]);
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (n, &i) in order.iter().enumerate() {
+        //only the ranges starting before this one ends can overlap it
+        for &j in order[n + 1..].iter() {
+            if ranges[j].0 > ranges[i].1 {
+                break;
+            }
+            pairs.push((i.min(j), i.max(j)));
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+        .into_iter()
+        .map(|(i, j)| (ranges[i], ranges[j]))
+        .collect()
+}
+
+fn read_ranges(input: File, radix: u32) -> Vec<(u64, u64)> {
+    read_range_list_in_radix(input, ReversedRanges::Reject, radix)
+        .unwrap_or_else(|e| panic!("unable to parse ranges: {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::Path;
 
+    #[test]
+    fn generated_ids_match_checking_every_id() {
+        for (start, end) in [(0, 2_000), (95_000, 125_000), (998_990, 1_001_020)] {
+            let expected: Vec<u64> = (start..=end).filter(|&i| !is_valid_id(i)).collect();
+            assert_eq!(get_invalid_ids_from_range(start, end), expected);
+            assert_eq!(
+                count_invalid_ids_in_range(start, end),
+                expected.len() as u64
+            );
+            let sum: u128 = expected.iter().map(|&i| u128::from(i)).sum();
+            assert_eq!(sum_invalid_ids_in_range(start, end), sum);
+        }
+    }
+
+    #[test]
+    fn huge_ranges_are_counted_without_scanning() {
+        assert_eq!(count_invalid_ids_in_range(0, u64::MAX), 1_845_675_350);
+        assert_eq!(count_invalid_ids_in_range(10, 9), 0);
+        // 2 or 3 digit units, minus the repdigits that are both
+        assert_eq!(count_invalid_ids_in_range(100_000, 999_999), 90 + 900 - 9);
+        assert_eq!(
+            sum_invalid_ids_in_range(999_999_999_999, 999_999_999_999),
+            999_999_999_999
+        );
+    }
+
+    #[test]
+    fn policies_match_checking_every_id() {
+        let policies = [
+            RepetitionPolicy::Exactly(2),
+            RepetitionPolicy::Exactly(3),
+            RepetitionPolicy::AtLeast(3),
+            RepetitionPolicy::Between(2, 3),
+            RepetitionPolicy::Between(4, 6),
+            RepetitionPolicy::Between(3, 2),
+            RepetitionPolicy::Exactly(0),
+            RepetitionPolicy::AtLeast(1),
+            RepetitionPolicy::Between(1, 2),
+            RepetitionPolicy::Exactly(1),
+        ];
+        for policy in policies {
+            for (start, end) in [(0, 1_200), (99_990, 100_010), (110_000, 112_000)] {
+                let expected: Vec<u64> = (start..=end)
+                    .filter(|&i| !is_valid_id_with_policy(i, policy))
+                    .collect();
+                let generated = get_invalid_ids_from_range_with_policy(start, end, policy);
+                assert_eq!(generated, expected, "{policy:?}");
+                let (count, sum) = invalid_id_totals(start, end, policy, 10);
+                assert_eq!(count, expected.len() as i128, "{policy:?}");
+                assert_eq!(
+                    sum,
+                    expected.iter().map(|&i| i128::from(i)).sum(),
+                    "{policy:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sum_invalid_ids_exactly_twice_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
+        let data = File::open(path).expect("test1.txt file missing");
+        let result = sum_invalid_ids_with_policy(data, RepetitionPolicy::Exactly(2));
+        assert_eq!(result, 1227775554);
+    }
+
+    #[test]
+    fn classify_id_agrees_with_is_valid_id() {
+        for id in (0..20_000).chain(999_990..1_001_010) {
+            let repetition = classify_id(id);
+            assert_eq!(repetition.repeats == 1, is_valid_id(id), "{id}");
+            let rebuilt = repetition
+                .unit
+                .to_string()
+                .repeat(repetition.repeats as usize);
+            assert_eq!(rebuilt, id.to_string());
+            assert_eq!(
+                repetition.unit_length as usize,
+                repetition.unit.to_string().len()
+            );
+        }
+    }
+
+    #[test]
+    fn sum_invalid_ids_report_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
+        let data = File::open(path).expect("test1.txt file missing");
+        let report = sum_invalid_ids_report(data, RepetitionPolicy::default());
+        assert_eq!(report.total, 4174379265);
+        assert_eq!(report.ranges.len(), 11);
+        let text = report.to_string();
+        assert!(text.starts_with("11-22: 2 invalid, sum 33\n  11 = 1 x2\n  22 = 2 x2\n"));
+        assert!(
+            text.contains("824824821-824824827: 1 invalid, sum 824824824\n  824824824 = 824 x3\n")
+        );
+        assert!(text.ends_with("total 4174379265\n"));
+    }
+
+    #[test]
+    fn sum_invalid_ids_counts_overlaps_once() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(path).expect("test2.txt file missing");
+        let result = sum_invalid_ids(data);
+        assert_eq!(result, 495 + 111);
+    }
+
+    #[test]
+    fn sum_invalid_ids_report_lists_overlaps() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(path).expect("test2.txt file missing");
+        let report = sum_invalid_ids_report(data, RepetitionPolicy::default());
+        assert_eq!(report.total, 606);
+        assert_eq!(report.ranges[1].sum, 44 + 55 + 66 + 77 + 88 + 99);
+        assert_eq!(
+            report.overlaps,
+            [((10, 50), (40, 100)), ((40, 100), (95, 115))]
+        );
+        assert!(report.to_string().contains("overlap 10-50 and 40-100\n"));
+    }
+
+    #[test]
+    fn merge_overlapping_ranges_handles_edges() {
+        assert!(merge_overlapping_ranges::<u64>(&mut []).is_empty());
+        let mut touching = vec![(5, 9), (1, 4), (3, 3), (9, 12)];
+        assert_eq!(merge_overlapping_ranges(&mut touching), [(1, 4), (5, 12)]);
+        assert_eq!(
+            overlapping_ranges(&[(5, 9), (1, 4), (3, 3), (9, 12)]),
+            [((5, 9), (9, 12)), ((1, 4), (3, 3))]
+        );
+    }
+
+    #[test]
+    fn parse_range_list_reports_bad_tokens() {
+        let check = |input: &str, offset: usize, token: &str, kind: RangeErrorKind| {
+            let error = parse_range_list(input, ReversedRanges::Reject).unwrap_err();
+            let expected = RangeParseError {
+                offset,
+                token: token.to_string(),
+                kind,
+            };
+            assert_eq!(error, expected, "{input:?}");
+        };
+        check("11-22,,95-115", 6, ",", RangeErrorKind::MissingNumber);
+        check(",11-22", 0, ",", RangeErrorKind::MissingNumber);
+        check("11-22, 95-", 10, "", RangeErrorKind::MissingNumber);
+        check("11-22 95-115", 6, "9", RangeErrorKind::UnexpectedCharacter);
+        check("11-22,\n 50-10,", 8, "50-10", RangeErrorKind::Reversed);
+        check(
+            "1-99999999999999999999",
+            2,
+            "99999999999999999999",
+            RangeErrorKind::NumberTooLarge,
+        );
+        check("1-2,é-3", 4, "é", RangeErrorKind::MissingNumber);
+        check("1-2 é", 4, "é", RangeErrorKind::UnexpectedCharacter);
+    }
+
+    #[test]
+    fn parse_range_list_accepts_messy_lists() {
+        assert_eq!(parse_range_list("", ReversedRanges::Reject), Ok(vec![]));
+        assert_eq!(parse_range_list(" \n", ReversedRanges::Reject), Ok(vec![]));
+        assert_eq!(
+            parse_range_list("11-22,95-115\n998-1012", ReversedRanges::Reject),
+            Ok(vec![(11, 22), (95, 115), (998, 1012)])
+        );
+        let messy = "\n 50-10 ,\r\n 3 -\n 4 ,";
+        assert_eq!(
+            parse_range_list(messy, ReversedRanges::Swap),
+            Ok(vec![(10, 50), (3, 4)])
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn parse_range_from_string_panics_on_missing_dash() {
+        parse_range_from_string(String::from("1122"));
+    }
+
+    #[test]
+    fn invalid_ids_agree_with_counting() {
+        let policies = [
+            RepetitionPolicy::default(),
+            RepetitionPolicy::Exactly(2),
+            RepetitionPolicy::Between(3, 5),
+        ];
+        for policy in policies {
+            for (start, end) in [(1, 1), (5, 200_000), (999_998_999_000, 1_000_001_000_100)] {
+                let mut count: u64 = 0;
+                let mut sum: u128 = 0;
+                let mut previous: Option<u64> = None;
+                for id in InvalidIds::new(start, end, policy) {
+                    assert!(previous < Some(id));
+                    assert!(!is_valid_id_with_policy(id, policy));
+                    count += 1;
+                    sum += u128::from(id);
+                    previous = Some(id);
+                }
+                assert_eq!(u128::from(count), count_invalid(start, end, policy));
+                assert_eq!(sum, sum_invalid(start, end, policy));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_is_invalid_when_one_repeat_is() {
+        for policy in [
+            RepetitionPolicy::AtLeast(1),
+            RepetitionPolicy::Between(1, 2),
+        ] {
+            assert!(!is_valid_id_with_policy(0, policy));
+            for (start, end) in [(0, 0), (0, 3), (0, 12_000)] {
+                let expected: Vec<u64> = (start..=end)
+                    .filter(|&i| !is_valid_id_with_policy(i, policy))
+                    .collect();
+                assert_eq!(expected[0], 0);
+                let generated: Vec<u64> = InvalidIds::new(start, end, policy).collect();
+                assert_eq!(generated, expected, "{policy:?}");
+                assert_eq!(count_invalid(start, end, policy), expected.len() as u128);
+                let sum: u128 = expected.iter().map(|&i| u128::from(i)).sum();
+                assert_eq!(sum_invalid(start, end, policy), sum);
+            }
+        }
+        let whole = count_invalid(0, u64::MAX, RepetitionPolicy::AtLeast(1));
+        assert_eq!(whole, u128::from(u64::MAX) + 1);
+    }
+
+    #[test]
+    fn invalid_ids_reach_the_end_of_u64() {
+        let policy = RepetitionPolicy::default();
+        let last = InvalidIds::new(u64::MAX - 10_000_000_000_000, u64::MAX, policy).last();
+        assert_eq!(last, Some(18_446_744_071_844_674_407));
+        assert_eq!(InvalidIds::new(10, 9, policy).next(), None);
+    }
+
+    #[test]
+    fn radix_ids_match_checking_every_id() {
+        let policies = [RepetitionPolicy::default(), RepetitionPolicy::Exactly(2)];
+        for radix in [2, 3, 16, 36] {
+            for policy in policies {
+                for (start, end) in [(0, 5_000), (46_000, 47_000)] {
+                    let expected: Vec<u64> = (start..=end)
+                        .filter(|&i| !is_valid_id_with_policy_in_radix(i, policy, radix))
+                        .collect();
+                    let generated = get_invalid_ids_from_range_in_radix(start, end, policy, radix);
+                    assert_eq!(generated, expected, "{radix} {policy:?}");
+                    let count = count_invalid_in_radix(start, end, policy, radix);
+                    assert_eq!(count, expected.len() as u128, "{radix} {policy:?}");
+                    let sum: u128 = expected.iter().map(|&i| u128::from(i)).sum();
+                    assert_eq!(sum_invalid_in_radix(start, end, policy, radix), sum);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn radix_two_covers_all_of_u64() {
+        let policy = RepetitionPolicy::AtLeast(1);
+        assert!(!is_valid_id_with_policy_in_radix(0, policy, 2));
+        let mut ids = InvalidIds::with_radix(0, u64::MAX, policy, 2);
+        assert_eq!(ids.next(), Some(0));
+        assert_eq!(ids.next(), Some(1));
+        assert_eq!(count_invalid_in_radix(0, 0, policy, 2), 1);
+        assert_eq!(
+            count_invalid_in_radix(1, u64::MAX, policy, 2),
+            u128::from(u64::MAX)
+        );
+        assert_eq!(
+            count_invalid_in_radix(0, u64::MAX, policy, 2),
+            u128::from(u64::MAX) + 1
+        );
+        let everything: u128 = u128::from(u64::MAX) * (u128::from(u64::MAX) + 1) / 2;
+        assert_eq!(sum_invalid_in_radix(0, u64::MAX, policy, 2), everything);
+    }
+
+    #[test]
+    #[should_panic]
+    fn radix_must_be_in_range() {
+        is_valid_id_in_radix(5, 37);
+    }
+
+    #[test]
+    fn sum_invalid_ids_in_radix_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test3.txt");
+        let data = File::open(path).expect("test3.txt file missing");
+        let result = sum_invalid_ids_in_radix(data, RepetitionPolicy::default(), 16);
+        assert_eq!(result, 0x11 + 0x22 + 0xff + 0xabab);
+    }
+
+    #[test]
+    fn native_check_matches_regex_up_to_ten_million() {
+        //the regex is slow in a debug build, so split the ids across threads
+        let threads: u64 = 8;
+        let chunk = 10_000_000 / threads;
+        std::thread::scope(|scope| {
+            for n in 0..threads {
+                scope.spawn(move || {
+                    for id in n * chunk..=(n + 1) * chunk {
+                        let policy = RepetitionPolicy::default();
+                        assert_eq!(
+                            is_valid_id(id),
+                            is_valid_id_by_regex(id, policy, 10),
+                            "{id}"
+                        );
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn native_check_matches_regex_for_policies_and_radixes() {
+        let policies = [
+            RepetitionPolicy::default(),
+            RepetitionPolicy::Exactly(2),
+            RepetitionPolicy::AtLeast(3),
+            RepetitionPolicy::Between(2, 3),
+            RepetitionPolicy::Between(3, 2),
+            RepetitionPolicy::AtLeast(0),
+        ];
+        for radix in [2, 7, 10, 16, 36] {
+            for policy in policies {
+                for id in (0..3_000).chain(u64::MAX - 1_000..=u64::MAX) {
+                    assert_eq!(
+                        is_valid_id_with_policy_in_radix(id, policy, radix),
+                        is_valid_id_by_regex(id, policy, radix),
+                        "{id} {radix} {policy:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_match_stepping_one_at_a_time() {
+        //None when the neighbour is too far away to step to in a test
+        let step_to = |from: u64, forward: bool, valid: bool| {
+            let mut id = from;
+            for _ in 0..2_000 {
+                let next = if forward {
+                    id.checked_add(1)
+                } else {
+                    id.checked_sub(1)
+                };
+                match next {
+                    None => return Some(None),
+                    Some(next) if is_valid_id(next) == valid => return Some(Some(next)),
+                    Some(next) => id = next,
+                }
+            }
+            None
+        };
+        let around_lengths = (1..20).flat_map(|length| {
+            let edge = 10u64.pow(length);
+            edge.saturating_sub(30)..edge + 30
+        });
+        for n in (0..3_000)
+            .chain(around_lengths)
+            .chain(u64::MAX - 30..=u64::MAX)
+        {
+            let checks = [
+                (next_valid_id(n), step_to(n, true, true)),
+                (prev_valid_id(n), step_to(n, false, true)),
+                (next_invalid_id(n), step_to(n, true, false)),
+                (prev_invalid_id(n), step_to(n, false, false)),
+            ];
+            for (found, stepped) in checks {
+                if let Some(expected) = stepped {
+                    assert_eq!(found, expected, "{n}");
+                }
+            }
+        }
+        assert_eq!(prev_invalid_id(9_999_999_999_970), Some(8_888_888_888_888));
+        let last = 18_446_744_071_844_674_407;
+        assert_eq!(next_invalid_id(last), None);
+        assert_eq!(next_invalid_id(last - 1), Some(last));
+        assert_eq!(prev_invalid_id(u64::MAX), Some(last));
+    }
+
+    #[test]
+    fn rank_and_select_match_enumeration() {
+        let policy = RepetitionPolicy::default();
+        for (k, id) in InvalidIds::new(0, 2_000_000, policy).enumerate() {
+            let k = k as u64 + 1;
+            assert_eq!(invalid_rank(id), k);
+            assert_eq!(invalid_rank(id - 1), k - 1);
+            if k % 97 == 1 {
+                assert_eq!(invalid_select(k), Some(id));
+            }
+        }
+        let last = invalid_rank(u64::MAX);
+        assert_eq!(invalid_select(last), Some(18_446_744_071_844_674_407));
+        assert_eq!(invalid_select(last + 1), None);
+        for id in [
+            999_999_999_999,
+            1_111_111_111_111,
+            12_341_234_123_412_341_234,
+        ] {
+            assert_eq!(invalid_select(invalid_rank(id)), Some(id));
+        }
+    }
+
+    #[test]
+    fn sum_invalid_ids_with_rule_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(path).expect("test2.txt file missing");
+        let result = sum_invalid_ids_with_rule(data, &RepetitionPolicy::default());
+        assert_eq!(result, 606);
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test2.txt");
+        let data = File::open(path).expect("test2.txt file missing");
+        let rule = Any(vec![
+            Box::new(RepetitionPolicy::default()),
+            Box::new(Palindrome),
+        ]);
+        let palindromes: u128 = [101, 111].iter().sum();
+        assert_eq!(
+            sum_invalid_ids_with_rule(data, &rule),
+            606 - 111 + palindromes
+        );
+    }
+
+    #[test]
+    fn twenty_digit_sums_do_not_overflow() {
+        let open = || {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test5.txt");
+            File::open(path).expect("test5.txt file missing")
+        };
+        let expected: u128 = 12_014_130_244_457_775_013_632_566_892;
+        assert!(expected > u128::from(u64::MAX));
+        let policy = RepetitionPolicy::default();
+        assert_eq!(sum_invalid_ids(open()), expected);
+        assert_eq!(sum_invalid_ids_with_policy(open(), policy), expected);
+        assert_eq!(sum_invalid_ids_in_radix(open(), policy, 10), expected);
+        assert_eq!(sum_invalid_ids_with_rule(open(), &policy), expected);
+        assert_eq!(sum_invalid_big_ids(open(), policy), BigUint::from(expected));
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test6.txt");
+        let data = File::open(path).expect("test6.txt file missing");
+        let report = sum_invalid_ids_report(data, policy);
+        let expected = 18_181_818_181_818_181_818 + 18_446_744_071_844_674_407;
+        assert_eq!(report.total, expected);
+        assert_eq!(report.ranges[1].sum, 18_446_744_071_844_674_407);
+        assert!(report.to_string().ends_with(&format!("total {expected}\n")));
+    }
+
+    #[test]
+    fn sum_invalid_big_ids_example_works() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
+        let data = File::open(path).expect("test1.txt file missing");
+        let result = sum_invalid_big_ids(data, RepetitionPolicy::default());
+        assert_eq!(result, BigUint::from(4174379265u64));
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test4.txt");
+        let data = File::open(path).expect("test4.txt file missing");
+        let result = sum_invalid_big_ids(data, RepetitionPolicy::default());
+        let expected = "495495262915620037842207576620768473062387121671";
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test]
+    fn mobius_works() {
+        let values: Vec<i32> = (1..=12).map(mobius).collect();
+        assert_eq!(values, [1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]);
+    }
+
     #[test]
     fn sum_invalid_ids_example_works() {
         let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test1.txt");
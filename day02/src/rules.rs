@@ -0,0 +1,290 @@
+use crate::{RepetitionPolicy, is_valid_id_with_policy, sum_invalid};
+
+/// A reason to reject an ID. Rules can be combined with Any, All and Not.
+///
+/// # Examples
+///
+/// ```
+/// use day02::{Any, IdRule, Palindrome, RepetitionPolicy, RunLongerThan};
+/// let rule = Any(vec![
+///     Box::new(RepetitionPolicy::default()),
+///     Box::new(Palindrome),
+///     Box::new(RunLongerThan(3)),
+/// ]);
+/// assert!(rule.is_invalid(123123));
+/// assert!(rule.is_invalid(12321));
+/// assert!(rule.is_invalid(1222234));
+/// assert!(!rule.is_invalid(1222345));
+/// ```
+pub trait IdRule {
+    /// Returns true if the rule rejects the id
+    fn is_invalid(&self, id: u64) -> bool;
+
+    /// Sums the ids the rule rejects in an inclusive range.
+    ///
+    /// Checks every id of the range unless the rule knows a faster way.
+    fn sum_range(&self, start: u64, end: u64) -> u128 {
+        if start > end {
+            return 0;
+        }
+        (start..=end)
+            .filter(|&id| self.is_invalid(id))
+            .map(u128::from)
+            .sum()
+    }
+}
+
+impl<R: IdRule + ?Sized> IdRule for &R {
+    fn is_invalid(&self, id: u64) -> bool {
+        (**self).is_invalid(id)
+    }
+
+    fn sum_range(&self, start: u64, end: u64) -> u128 {
+        (**self).sum_range(start, end)
+    }
+}
+
+impl<R: IdRule + ?Sized> IdRule for Box<R> {
+    fn is_invalid(&self, id: u64) -> bool {
+        (**self).is_invalid(id)
+    }
+
+    fn sum_range(&self, start: u64, end: u64) -> u128 {
+        (**self).sum_range(start, end)
+    }
+}
+
+/// Rejects ids made of a substring repeated a number of times the policy forbids
+impl IdRule for RepetitionPolicy {
+    fn is_invalid(&self, id: u64) -> bool {
+        !is_valid_id_with_policy(id, *self)
+    }
+
+    fn sum_range(&self, start: u64, end: u64) -> u128 {
+        sum_invalid(start, end, *self)
+    }
+}
+
+/// Rejects ids of two or more digits that read the same backwards
+///
+/// # Examples
+///
+/// ```
+/// use day02::{IdRule, Palindrome};
+/// assert!(Palindrome.is_invalid(1234321));
+/// assert!(!Palindrome.is_invalid(7));
+/// assert!(!Palindrome.is_invalid(1230));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Palindrome;
+
+impl IdRule for Palindrome {
+    fn is_invalid(&self, id: u64) -> bool {
+        let (digits, length) = decimal_digits(id);
+        let digits = &digits[..length];
+        length > 1 && digits.iter().eq(digits.iter().rev())
+    }
+}
+
+/// Rejects ids with more than this many of the same digit in a row
+///
+/// # Examples
+///
+/// ```
+/// use day02::{IdRule, RunLongerThan};
+/// assert!(RunLongerThan(2).is_invalid(1000));
+/// assert!(!RunLongerThan(2).is_invalid(1001));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RunLongerThan(pub u32);
+
+impl IdRule for RunLongerThan {
+    fn is_invalid(&self, id: u64) -> bool {
+        let (digits, length) = decimal_digits(id);
+        let mut run: u32 = 0;
+        for (i, digit) in digits[..length].iter().enumerate() {
+            if i > 0 && digits[i - 1] == *digit {
+                run += 1;
+            } else {
+                run = 1;
+            }
+            if run > self.0 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Rejects ids that the policy allows but that become forbidden repeats when a single
+/// digit is changed, like 123124
+///
+/// # Examples
+///
+/// ```
+/// use day02::{AlmostRepeated, IdRule};
+/// let rule = AlmostRepeated::default();
+/// assert!(rule.is_invalid(123124));
+/// assert!(rule.is_invalid(1211));
+/// assert!(!rule.is_invalid(123123));
+/// assert!(!rule.is_invalid(1234));
+/// // the only fix would be a leading zero
+/// assert!(!rule.is_invalid(100));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AlmostRepeated(pub RepetitionPolicy);
+
+impl IdRule for AlmostRepeated {
+    fn is_invalid(&self, id: u64) -> bool {
+        if !is_valid_id_with_policy(id, self.0) {
+            return false;
+        }
+        let (digits, length) = decimal_digits(id);
+        let digits = &digits[..length];
+        (1..length)
+            .filter(|&unit_length| length.is_multiple_of(unit_length))
+            .filter(|&unit_length| self.0.allows((length / unit_length) as u32))
+            .any(|unit_length| changes_to_repeat(digits, unit_length) == 1)
+    }
+}
+
+// How many digits have to change to make the id a unit of `unit_length` repeated. Each
+// digit of the unit is copied to every `unit_length`th place, so every place keeps
+// whichever digit is most common among its copies.
+fn changes_to_repeat(digits: &[u8], unit_length: usize) -> usize {
+    let repeats = digits.len() / unit_length;
+    let mut changes = 0;
+    for place in 0..unit_length {
+        let mut counts = [0usize; 10];
+        for &digit in digits[place..].iter().step_by(unit_length) {
+            counts[usize::from(digit)] += 1;
+        }
+        //the id can't be made to start with a zero
+        if place == 0 {
+            counts[0] = 0;
+        }
+        changes += repeats - counts.iter().max().expect("ten digits");
+    }
+    changes
+}
+
+/// Rejects ids that any of the rules reject. An empty list rejects nothing.
+pub struct Any(pub Vec<Box<dyn IdRule>>);
+
+impl IdRule for Any {
+    fn is_invalid(&self, id: u64) -> bool {
+        self.0.iter().any(|rule| rule.is_invalid(id))
+    }
+}
+
+/// Rejects ids that all of the rules reject. An empty list rejects everything.
+///
+/// # Examples
+///
+/// ```
+/// use day02::{All, IdRule, Palindrome, RepetitionPolicy};
+/// let rule = All(vec![Box::new(RepetitionPolicy::default()), Box::new(Palindrome)]);
+/// assert!(rule.is_invalid(1111));
+/// assert!(!rule.is_invalid(1212));
+/// ```
+pub struct All(pub Vec<Box<dyn IdRule>>);
+
+impl IdRule for All {
+    fn is_invalid(&self, id: u64) -> bool {
+        self.0.iter().all(|rule| rule.is_invalid(id))
+    }
+}
+
+/// Rejects exactly the ids the inner rule accepts
+///
+/// # Examples
+///
+/// ```
+/// use day02::{IdRule, Not, RunLongerThan};
+/// // some digit has to appear twice in a row
+/// let rule = Not(RunLongerThan(1));
+/// assert!(rule.is_invalid(12));
+/// assert!(!rule.is_invalid(112));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Not<R: IdRule>(pub R);
+
+impl<R: IdRule> IdRule for Not<R> {
+    fn is_invalid(&self, id: u64) -> bool {
+        !self.0.is_invalid(id)
+    }
+}
+
+// the decimal digits of the id, most significant first, and how many of them there are
+fn decimal_digits(id: u64) -> ([u8; 20], usize) {
+    let mut digits = [0u8; 20];
+    let mut length = 0;
+    let mut remaining = id;
+    loop {
+        digits[length] = (remaining % 10) as u8;
+        length += 1;
+        remaining /= 10;
+        if remaining == 0 {
+            break;
+        }
+    }
+    digits[..length].reverse();
+    (digits, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the brute force version: try every single digit change
+    fn one_change_from_repeat(id: u64, policy: RepetitionPolicy) -> bool {
+        let digits = id.to_string().into_bytes();
+        for place in 0..digits.len() {
+            for digit in b'0'..=b'9' {
+                if digit == digits[place] || (place == 0 && digit == b'0') {
+                    continue;
+                }
+                let mut changed = digits.clone();
+                changed[place] = digit;
+                let changed: u64 = String::from_utf8(changed).unwrap().parse().unwrap();
+                if !is_valid_id_with_policy(changed, policy) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn almost_repeated_matches_trying_every_change() {
+        for policy in [RepetitionPolicy::default(), RepetitionPolicy::Exactly(3)] {
+            let rule = AlmostRepeated(policy);
+            for id in (0..30_000).chain(999_000..1_001_000) {
+                let expected =
+                    is_valid_id_with_policy(id, policy) && one_change_from_repeat(id, policy);
+                assert_eq!(rule.is_invalid(id), expected, "{id} {policy:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn sum_range_uses_the_rule() {
+        let not_repeated = Not(RepetitionPolicy::default());
+        assert_eq!(RepetitionPolicy::default().sum_range(95, 115), 210);
+        assert_eq!(
+            not_repeated.sum_range(95, 115),
+            (95..=115).sum::<u128>() - 210
+        );
+        assert_eq!(Any(vec![]).sum_range(1, 1_000), 0);
+        assert_eq!(All(vec![]).sum_range(1, 100), 5050);
+        assert_eq!(Palindrome.sum_range(10, 9), 0);
+    }
+
+    #[test]
+    fn decimal_digits_works() {
+        assert_eq!(decimal_digits(0).1, 1);
+        let (digits, length) = decimal_digits(u64::MAX);
+        assert_eq!(length, 20);
+        assert_eq!(digits[..3], [1, 8, 4]);
+    }
+}